@@ -0,0 +1,225 @@
+use std::sync::OnceLock;
+
+pub const DEFAULT_CAP: u32 = 64;
+
+/// Exact outcome distributions of full battles, where the attacker keeps attacking with the
+/// maximum number of dice until either side runs out of troops.
+///
+/// Attackers are the troops that are able to attack, so they exclude the troop that must be left
+/// behind in the attacking territory. Outcomes are only available for up to `cap` attackers and
+/// defenders.
+pub struct BattleOdds {
+    cap: u32,
+    offsets: Vec<usize>,
+    // Outcomes are ordered from worst to best for the attacker. The first `defenders` outcomes
+    // are losses with `i` defenders lost, and the remaining outcomes are wins with `j + 1`
+    // attackers remaining.
+    cdf: Vec<f64>,
+    win_probability: Vec<f64>,
+    expected_survivors: Vec<(f64, f64)>,
+}
+
+impl BattleOdds {
+    pub fn new(cap: u32) -> Self {
+        let rounds = round_probabilities();
+        let cap_usize = cap as usize;
+
+        let mut offsets = vec![0; cap_usize * cap_usize + 1];
+        for attackers in 1..=cap {
+            for defenders in 1..=cap {
+                let index = (attackers as usize - 1) * cap_usize + defenders as usize - 1;
+                offsets[index + 1] = offsets[index] + (attackers + defenders) as usize;
+            }
+        }
+
+        let mut pmf = vec![0.0; offsets[cap_usize * cap_usize]];
+        for attackers in 1..=cap {
+            for defenders in 1..=cap {
+                let index = (attackers as usize - 1) * cap_usize + defenders as usize - 1;
+                let num_attackers = std::cmp::min(attackers, 3);
+                let num_defenders = std::cmp::min(defenders, 2);
+                let battles = std::cmp::min(num_attackers, num_defenders);
+
+                let (before, after) = pmf.split_at_mut(offsets[index]);
+                let outcomes = &mut after[..(attackers + defenders) as usize];
+                for attackers_lost in 0..=battles {
+                    let p = rounds[num_attackers as usize - 1][num_defenders as usize - 1]
+                        [attackers_lost as usize];
+                    if p == 0.0 {
+                        continue;
+                    }
+
+                    let remaining_attackers = attackers - attackers_lost;
+                    let remaining_defenders = defenders - (battles - attackers_lost);
+                    let d = defenders as usize;
+
+                    if remaining_attackers == 0 {
+                        outcomes[d - remaining_defenders as usize] += p;
+                    } else if remaining_defenders == 0 {
+                        outcomes[d + remaining_attackers as usize - 1] += p;
+                    } else {
+                        let next = (remaining_attackers as usize - 1) * cap_usize
+                            + remaining_defenders as usize
+                            - 1;
+                        let next_outcomes = &before[offsets[next]..offsets[next + 1]];
+                        let next_d = remaining_defenders as usize;
+
+                        for (i, &q) in next_outcomes[..next_d].iter().enumerate() {
+                            outcomes[d - next_d + i] += p * q;
+                        }
+
+                        for (j, &q) in next_outcomes[next_d..].iter().enumerate() {
+                            outcomes[d + j] += p * q;
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut cdf = pmf.clone();
+        let mut win_probability = vec![0.0; cap_usize * cap_usize];
+        let mut expected_survivors = vec![(0.0, 0.0); cap_usize * cap_usize];
+        for attackers in 1..=cap {
+            for defenders in 1..=cap {
+                let index = (attackers as usize - 1) * cap_usize + defenders as usize - 1;
+                let outcomes = &pmf[offsets[index]..offsets[index + 1]];
+                let d = defenders as usize;
+
+                win_probability[index] = outcomes[d..].iter().sum();
+                expected_survivors[index] = (
+                    outcomes[d..]
+                        .iter()
+                        .enumerate()
+                        .map(|(j, p)| (j + 1) as f64 * p)
+                        .sum(),
+                    outcomes[..d]
+                        .iter()
+                        .enumerate()
+                        .map(|(i, p)| (d - i) as f64 * p)
+                        .sum(),
+                );
+
+                let mut total = 0.0;
+                for value in &mut cdf[offsets[index]..offsets[index + 1]] {
+                    total += *value;
+                    *value = total;
+                }
+            }
+        }
+
+        Self {
+            cap,
+            offsets,
+            cdf,
+            win_probability,
+            expected_survivors,
+        }
+    }
+
+    pub fn cap(&self) -> u32 {
+        self.cap
+    }
+
+    pub fn win_probability(&self, attackers: u32, defenders: u32) -> Option<f64> {
+        self.index(attackers, defenders)
+            .map(|index| self.win_probability[index])
+    }
+
+    /// The expected number of attackers and defenders remaining after the battle
+    pub fn expected_survivors(&self, attackers: u32, defenders: u32) -> Option<(f64, f64)> {
+        self.index(attackers, defenders)
+            .map(|index| self.expected_survivors[index])
+    }
+
+    /// The probability of each outcome, as `((attackers_lost, defenders_lost), probability)`
+    pub fn outcomes(&self, attackers: u32, defenders: u32) -> Option<Vec<((u32, u32), f64)>> {
+        let index = self.index(attackers, defenders)?;
+        let cdf = &self.cdf[self.offsets[index]..self.offsets[index + 1]];
+
+        let mut last = 0.0;
+        let outcomes = cdf
+            .iter()
+            .enumerate()
+            .map(|(i, &total)| {
+                let probability = total - last;
+                last = total;
+                (outcome(attackers, defenders, i), probability)
+            })
+            .collect();
+
+        Some(outcomes)
+    }
+
+    /// Samples the result of a full battle, as `(attackers_lost, defenders_lost)`
+    pub fn sample(
+        &self,
+        attackers: u32,
+        defenders: u32,
+        rng: &mut impl rand::Rng,
+    ) -> Option<(u32, u32)> {
+        let index = self.index(attackers, defenders)?;
+        let cdf = &self.cdf[self.offsets[index]..self.offsets[index + 1]];
+
+        let value = rng.gen::<f64>() * cdf[cdf.len() - 1];
+        let i = cdf.partition_point(|&x| x <= value).min(cdf.len() - 1);
+        Some(outcome(attackers, defenders, i))
+    }
+
+    fn index(&self, attackers: u32, defenders: u32) -> Option<usize> {
+        ((1..=self.cap).contains(&attackers) && (1..=self.cap).contains(&defenders))
+            .then(|| (attackers as usize - 1) * self.cap as usize + defenders as usize - 1)
+    }
+}
+
+/// Shared battle odds, computed on first use
+pub fn odds() -> &'static BattleOdds {
+    static ODDS: OnceLock<BattleOdds> = OnceLock::new();
+    ODDS.get_or_init(|| BattleOdds::new(DEFAULT_CAP))
+}
+
+fn outcome(attackers: u32, defenders: u32, i: usize) -> (u32, u32) {
+    let i = i as u32;
+    if i < defenders {
+        (attackers, i)
+    } else {
+        (attackers - (i - defenders + 1), defenders)
+    }
+}
+
+/// The probability of the attacker losing each number of troops in a single round, indexed by
+/// the number of attacking and defending dice
+fn round_probabilities() -> [[[f64; 3]; 2]; 3] {
+    let mut probabilities = [[[0.0; 3]; 2]; 3];
+    for (num_attackers, probabilities) in (1..=3).zip(&mut probabilities) {
+        for (num_defenders, probabilities) in (1..=2).zip(probabilities) {
+            let total = 6_u32.pow(num_attackers + num_defenders);
+            for roll in 0..total {
+                let mut dice = roll;
+                let mut roll_dice = |count| {
+                    let mut values = (0..count)
+                        .map(|_| {
+                            let value = dice % 6;
+                            dice /= 6;
+                            value
+                        })
+                        .collect::<Vec<_>>();
+
+                    values.sort_unstable_by_key(|&x| std::cmp::Reverse(x));
+                    values
+                };
+
+                let attacking_rolls = roll_dice(num_attackers);
+                let defending_rolls = roll_dice(num_defenders);
+                let attackers_lost = attacking_rolls
+                    .iter()
+                    .zip(&defending_rolls)
+                    .filter(|(x, y)| x <= y)
+                    .count();
+
+                probabilities[attackers_lost] += f64::from(total).recip();
+            }
+        }
+    }
+
+    probabilities
+}
@@ -0,0 +1,172 @@
+use risk_shared::{
+    calculate_set_bonus,
+    map::{TerritoryId, EDGES},
+    Card, CardSymbol,
+};
+
+use crate::game::AttackGame;
+
+/// The bonus for redeeming a card set into a territory we own
+pub const MATCHING_TERRITORY_BONUS: u32 = 2;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Params {
+    /// The value of holding a set until our next turn, relative to redeeming it now
+    pub hold_discount: f64,
+    /// How much more troops are worth now when our border is outnumbered
+    pub pressure_weight: f64,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            hold_discount: 0.9,
+            pressure_weight: 0.5,
+        }
+    }
+}
+
+tunable::tunable! {
+    impl Tunable for Params {
+        hold_discount: float(0.0, 2.0, 0.05, 0.01),
+        pressure_weight: float(0.0, 2.0, 0.05, 0.01),
+    }
+}
+
+/// Chooses the card sets to redeem. Sets are always redeemed while we hold at least 5 cards, and
+/// if `optional` is set, further sets are redeemed when the troops are worth more now than the
+/// larger bonus we expect to get by waiting for the other players to redeem their sets first.
+/// The game is only used for the territories and the number of sets redeemed.
+pub fn choose_redemption(
+    game: &AttackGame,
+    cards: &[Card],
+    opponent_card_counts: &[usize],
+    optional: bool,
+    params: &Params,
+) -> Vec<[Card; 3]> {
+    let mut sets = Vec::new();
+    let mut cards_remaining = cards.to_vec();
+    let mut card_sets_redeemed = game.card_sets_redeemed();
+    let mut matched = false;
+
+    while cards_remaining.len() >= 5 {
+        let set = best_card_set(game, &cards_remaining).unwrap();
+        matched |= matches_territory(game, set);
+        cards_remaining.retain(|x| !set.contains(x));
+        sets.push(set);
+        card_sets_redeemed += 1;
+    }
+
+    if !optional {
+        return sets;
+    }
+
+    let urgency = 1.0 + params.pressure_weight * border_pressure(game);
+    let escalation = expected_redemptions(opponent_card_counts);
+    while let Some(set) = best_card_set(game, &cards_remaining) {
+        let matching_bonus = if !matched && matches_territory(game, set) {
+            MATCHING_TERRITORY_BONUS
+        } else {
+            0
+        };
+
+        let now = f64::from(calculate_set_bonus(card_sets_redeemed) + matching_bonus) * urgency;
+        let later =
+            params.hold_discount * f64::from(calculate_set_bonus(card_sets_redeemed + escalation));
+        if now < later {
+            break;
+        }
+
+        matched |= matching_bonus > 0;
+        cards_remaining.retain(|x| !set.contains(x));
+        sets.push(set);
+        card_sets_redeemed += 1;
+    }
+
+    sets
+}
+
+/// Finds a valid set, preferring sets that match one of our territories and use fewer wildcards
+pub fn best_card_set(game: &AttackGame, cards: &[Card]) -> Option<[Card; 3]> {
+    let mut best = None;
+    for i in 0..cards.len() {
+        for j in i + 1..cards.len() {
+            for k in j + 1..cards.len() {
+                let set = [cards[i], cards[j], cards[k]];
+                if !is_card_set(set) {
+                    continue;
+                }
+
+                let wildcards = set
+                    .iter()
+                    .filter(|x| x.symbol() == CardSymbol::Wildcard)
+                    .count();
+                let key = (matches_territory(game, set), std::cmp::Reverse(wildcards));
+                if best.is_none_or(|(best_key, _)| key > best_key) {
+                    best = Some((key, set));
+                }
+            }
+        }
+    }
+
+    best.map(|(_, set)| set)
+}
+
+pub fn is_card_set(set: [Card; 3]) -> bool {
+    let symbols = set
+        .iter()
+        .map(|x| x.symbol())
+        .filter(|&x| x != CardSymbol::Wildcard)
+        .collect::<Vec<_>>();
+
+    let all_same = symbols.windows(2).all(|x| x[0] == x[1]);
+    let all_different = (0..symbols.len()).all(|i| !symbols[i + 1..].contains(&symbols[i]));
+    all_same || all_different
+}
+
+fn matches_territory(game: &AttackGame, set: [Card; 3]) -> bool {
+    set.iter()
+        .filter_map(|x| x.territory())
+        .any(|t| game.occupier(t).is_p0())
+}
+
+/// The number of sets we expect the other players to redeem before our next turn
+fn expected_redemptions(opponent_card_counts: &[usize]) -> u32 {
+    let expected = opponent_card_counts
+        .iter()
+        .map(|&count| match count {
+            0..=2 => 0.0,
+            3..=4 => 0.5,
+            _ => 1.0,
+        })
+        .sum::<f64>();
+
+    expected.round() as u32
+}
+
+/// How outnumbered our border territories are by their neighbours, where zero is even
+fn border_pressure(game: &AttackGame) -> f64 {
+    let mut ours = 0;
+    let mut theirs = 0;
+    for territory in TerritoryId::ALL
+        .into_iter()
+        .filter(|&t| game.occupier(t).is_p0())
+    {
+        let enemy_troops = EDGES[territory]
+            .iter()
+            .filter(|&&t| !game.occupier(t).is_p0())
+            .map(|&t| game.troops(t))
+            .max();
+
+        if let Some(enemy_troops) = enemy_troops {
+            ours += game.troops(territory);
+            theirs += enemy_troops;
+        }
+    }
+
+    if ours == 0 {
+        0.0
+    } else {
+        f64::max(0.0, f64::from(theirs) / f64::from(ours) - 1.0)
+    }
+}
@@ -0,0 +1,283 @@
+use enum_map::EnumMap;
+use mcts::multiplayer::{self, Backup, Rewards, MAX_PLAYERS};
+use rand::prelude::SliceRandom;
+use risk_shared::{
+    map::{Continent, TerritoryId, EDGES},
+    player::PlayerId,
+};
+
+/// The number of random territories considered by each claim in a rollout
+const ROLLOUT_CANDIDATES: usize = 3;
+
+/// The initial claim phase, where players take turns claiming one unclaimed territory each
+#[derive(Clone, Debug)]
+pub struct DraftGame {
+    claims: EnumMap<TerritoryId, Option<PlayerId>>,
+    turn: PlayerId,
+    num_players: u8,
+}
+
+impl DraftGame {
+    /// Player ids are based on turn order, and the player to move is `turn`
+    pub fn new(
+        claims: EnumMap<TerritoryId, Option<PlayerId>>,
+        turn: PlayerId,
+        num_players: u8,
+    ) -> Self {
+        assert!(num_players as usize <= MAX_PLAYERS);
+        Self {
+            claims,
+            turn,
+            num_players,
+        }
+    }
+
+    pub fn claim(&mut self, territory: TerritoryId) {
+        assert!(self.claims[territory].is_none());
+        self.claims[territory] = Some(self.turn);
+        self.turn = PlayerId::n((self.turn as u8 + 1) % self.num_players).unwrap();
+    }
+
+    pub fn unclaimed(&self) -> impl Iterator<Item = TerritoryId> + '_ {
+        TerritoryId::ALL
+            .into_iter()
+            .filter(|&t| self.claims[t].is_none())
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.claims.values().all(Option::is_some)
+    }
+
+    pub fn turn(&self) -> PlayerId {
+        self.turn
+    }
+
+    pub fn claimant(&self, territory: TerritoryId) -> Option<PlayerId> {
+        self.claims[territory]
+    }
+
+    pub fn num_players(&self) -> u8 {
+        self.num_players
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Params {
+    pub iterations: u32,
+    pub exploration: f64,
+    /// Scaled by the bonus of each continent that is held entirely
+    pub continent_complete: f64,
+    /// Scaled by the bonus of each continent and the square of the share held
+    pub continent_share: f64,
+    /// Scaled by the proportion of territories that border another player
+    pub border: f64,
+    /// Scaled by the number of edges between our own territories per territory
+    pub adjacency: f64,
+    pub temperature: f64,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            iterations: 2000,
+            exploration: 0.3,
+            continent_complete: 1.0,
+            continent_share: 0.5,
+            border: 1.0,
+            adjacency: 0.5,
+            temperature: 1.0,
+        }
+    }
+}
+
+tunable::tunable! {
+    impl Tunable for Params {
+        iterations: fixed,
+        exploration: float(0.0, 2.0, 0.05, 0.01),
+        continent_complete: float(0.0, 10.0, 0.05, 0.01),
+        continent_share: float(0.0, 10.0, 0.05, 0.01),
+        border: float(0.0, 10.0, 0.05, 0.01),
+        adjacency: float(0.0, 10.0, 0.05, 0.01),
+        temperature: float(0.1, 10.0, 0.05, 0.01),
+    }
+}
+
+/// Scores the end of the draft for every player, as a softmax over the strength of their
+/// territories
+pub fn evaluate(game: &DraftGame, params: &Params) -> Rewards {
+    let mut territories = [0_u32; MAX_PLAYERS];
+    let mut borders = [0_u32; MAX_PLAYERS];
+    let mut edges = [0_u32; MAX_PLAYERS];
+    let mut continents = EnumMap::from_fn(|_| [0_u32; MAX_PLAYERS]);
+
+    for territory in TerritoryId::ALL {
+        let Some(player) = game.claimant(territory) else {
+            continue;
+        };
+
+        let player = player as usize;
+        territories[player] += 1;
+        continents[territory.continent()][player] += 1;
+
+        let mut border = false;
+        for &adjacent in EDGES[territory] {
+            match game.claimant(adjacent) {
+                Some(x) if x as usize == player => edges[player] += 1,
+                Some(_) => border = true,
+                None => (),
+            }
+        }
+
+        if border {
+            borders[player] += 1;
+        }
+    }
+
+    let mut scores = [0.0; MAX_PLAYERS];
+    let num_players = game.num_players() as usize;
+    for (player, score) in scores.iter_mut().enumerate().take(num_players) {
+        for continent in Continent::ALL {
+            let held = continents[continent][player];
+            let bonus = f64::from(continent.bonus());
+            if held == continent.territory_count() {
+                *score += params.continent_complete * bonus;
+            } else {
+                let share = f64::from(held) / f64::from(continent.territory_count());
+                *score += params.continent_share * bonus * share * share;
+            }
+        }
+
+        if territories[player] > 0 {
+            let count = f64::from(territories[player]);
+            *score -= params.border * f64::from(borders[player]) / count;
+            *score += params.adjacency * f64::from(edges[player]) / count;
+        }
+
+        *score *= params.temperature;
+    }
+
+    mcts::uct::softmax(&mut scores[..num_players]);
+    scores
+}
+
+/// Searches the draft, where every player maximises their own score
+pub fn search(game: DraftGame, params: Params, rng: &mut impl rand::Rng) -> Option<TerritoryId> {
+    let mut mcts = mcts::MCTS::new(DraftSearch {
+        params,
+        root_game: game.clone(),
+        game,
+        rng,
+        rewards: [0.0; MAX_PLAYERS],
+        candidates: Vec::new(),
+    });
+
+    for _ in 0..params.iterations {
+        mcts.add_node();
+    }
+
+    mcts.most_visits().map(|(&territory, _)| territory)
+}
+
+struct DraftSearch<'r, R: rand::Rng> {
+    params: Params,
+    root_game: DraftGame,
+    game: DraftGame,
+    rng: &'r mut R,
+    rewards: Rewards,
+    candidates: Vec<TerritoryId>, // Cache this vec
+}
+
+impl<R: rand::Rng> DraftSearch<'_, R> {
+    fn rollout_claim(&mut self) -> TerritoryId {
+        self.candidates.clear();
+        self.candidates.extend(self.game.unclaimed());
+
+        let turn = self.game.turn();
+        let game = &self.game;
+        *self
+            .candidates
+            .choose_multiple(self.rng, ROLLOUT_CANDIDATES)
+            .max_by_key(|&&t| {
+                EDGES[t]
+                    .iter()
+                    .filter(|&&x| game.claimant(x) == Some(turn))
+                    .count()
+            })
+            .unwrap()
+    }
+}
+
+impl<R: rand::Rng> mcts::Strategy for DraftSearch<'_, R> {
+    type Action = TerritoryId;
+    type Data = ();
+
+    fn reset(&mut self) {
+        self.game = self.root_game.clone();
+    }
+
+    fn move_root(&mut self, action: &Self::Action) {
+        self.root_game.claim(*action);
+        self.reset();
+    }
+
+    fn make_move(&mut self, action: &Self::Action) {
+        self.game.claim(*action);
+    }
+
+    fn select(&mut self, node: &mcts::Node<Self::Action, Self::Data>) -> mcts::Selection {
+        if node.children.is_empty() {
+            self.rewards = evaluate(&self.game, &self.params);
+            return mcts::Selection::Terminal(self.rewards[PlayerId::P0 as usize]);
+        }
+
+        let selection = node
+            .children
+            .iter()
+            .position(|(_, _, child)| child.is_none())
+            .unwrap_or_else(|| {
+                let parent_visits = f64::from(node.visits).ln();
+                (0..node.children.len())
+                    .max_by(|&x, &y| {
+                        let uct = |i: usize| {
+                            let child = node.children[i].2.as_ref().unwrap();
+                            let visits = f64::from(child.visits);
+                            child.score / visits
+                                + self.params.exploration * (parent_visits / visits).sqrt()
+                        };
+
+                        uct(x).total_cmp(&uct(y))
+                    })
+                    .unwrap()
+            });
+
+        self.game.claim(node.children[selection].0);
+        mcts::Selection::Selection(selection as u32)
+    }
+
+    fn expand(
+        &mut self,
+        _: &mcts::Node<Self::Action, Self::Data>,
+    ) -> Vec<(Self::Action, Self::Data)> {
+        let mut actions = self.game.unclaimed().map(|t| (t, ())).collect::<Vec<_>>();
+        actions.shuffle(self.rng);
+        actions
+    }
+
+    fn simulate(&mut self, _: &mcts::Node<Self::Action, Self::Data>) -> f64 {
+        while !self.game.is_finished() {
+            let territory = self.rollout_claim();
+            self.game.claim(territory);
+        }
+
+        self.rewards = evaluate(&self.game, &self.params);
+        self.rewards[PlayerId::P0 as usize]
+    }
+
+    fn backpropagate(&mut self, _: f64, tree: mcts::TreeWalker<'_, '_, Self::Action, Self::Data>) {
+        multiplayer::backpropagate(&self.rewards, Backup::MaxN, tree);
+    }
+
+    fn player_to_move(&self) -> u8 {
+        self.game.turn() as u8
+    }
+}
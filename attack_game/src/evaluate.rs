@@ -33,6 +33,48 @@ pub struct Params {
     pub bias: Eval,
     pub resolve_k: f64,
     pub continent_by_player: EnumMap<Continent, EnumMap<PlayerId, Eval>>,
+    /// The strength of each opponent, from the strongest to the weakest
+    pub opponent_by_rank: [OpponentParams; 4],
+    /// Scaled by how far the strongest opponent is ahead of us for each player eliminated
+    pub leader_elimination: Eval,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct OpponentParams {
+    /// The share of the territories held by the opponent
+    pub territories: Eval,
+    /// The share of the troops held by the opponent
+    pub troops: Eval,
+    /// The share of the income received by the opponent
+    pub income: Eval,
+    /// The number of cards held by the opponent
+    pub cards: Eval,
+}
+
+impl Default for OpponentParams {
+    fn default() -> Self {
+        Self {
+            territories: Eval(0.0, 0.0),
+            troops: Eval(0.0, 0.0),
+            income: Eval(0.0, 0.0),
+            cards: Eval(0.0, 0.0),
+        }
+    }
+}
+
+impl OpponentParams {
+    fn evals(&self) -> [Eval; 4] {
+        [self.territories, self.troops, self.income, self.cards]
+    }
+
+    fn evals_mut(&mut self) -> [&mut Eval; 4] {
+        [
+            &mut self.territories,
+            &mut self.troops,
+            &mut self.income,
+            &mut self.cards,
+        ]
+    }
 }
 
 impl Default for Params {
@@ -90,76 +132,330 @@ impl Default for Params {
                     Eval(-0.32683830722755913, -0.06966700121303006),
                 ]),
             ]),
+            opponent_by_rank: [OpponentParams::default(); 4],
+            leader_elimination: Eval(0.0, 0.0),
         }
     }
 }
 
-pub fn evaluate(game: &AttackGame, params: &Params) -> f64 {
-    let mut territories_occupied = 0;
-    let mut continent_territories_occupied = EnumMap::from_fn(|_| EnumMap::from_fn(|_| 0));
-    let mut weak_territories = 0;
-    let mut isolated_territories = 0;
-    let mut my_troops = 0;
-    let mut total_troops = 0;
-
-    for territory in TerritoryId::ALL {
-        let troops = game.troops(territory);
-        if game.occupier(territory).is_p0() {
-            territories_occupied += 1;
-            my_troops += troops;
-            total_troops += troops;
-
-            let mut weak = false;
-            let mut isolated = true;
-            for &t in EDGES[territory] {
-                if game.occupier(t).is_p0() {
-                    isolated = false;
-                } else if troops == 1 {
-                    weak = true;
+tunable::tunable! {
+    impl Tunable for Params {
+        territory_occupied: eval(0.0, 10.0, 0.005, 0.01),
+        weak_territory: eval(-10.0, 0.0, 0.005, 0.01),
+        isolated_territory: eval(-10.0, 0.0, 0.005, 0.01),
+        player_eliminated: eval(0.0, 10.0, 0.05, 0.01),
+        territory_conquered: eval(0.0, 10.0, 0.05, 0.01),
+        troop_count: eval(0.0, 10.0, 0.05, 0.01),
+        bias: eval(-10.0, 10.0, 0.05, 0.01),
+        resolve_k: float(0.0, 10.0, 0.01, 0.01),
+        continent_by_player: with(continents),
+        opponent_by_rank: with(opponents),
+        leader_elimination: eval(-10.0, 0.0, 0.05, 0.01),
+    }
+}
+
+tunable::tunable! {
+    impl Tunable for OpponentParams {
+        territories: eval(-10.0, 10.0, 0.05, 0.01),
+        troops: eval(-10.0, 10.0, 0.05, 0.01),
+        income: eval(-10.0, 10.0, 0.05, 0.01),
+        cards: eval(-1.0, 1.0, 0.005, 0.01),
+    }
+}
+
+/// Named `opp<rank>_<feature>_<phase>`, where the strongest opponent has rank 0
+mod opponents {
+    use tunable::{Param, Tunable};
+
+    use super::OpponentParams;
+
+    pub fn params(opponents: &[OpponentParams; 4], prefix: &str, params: &mut Vec<Param>) {
+        for (rank, opponent) in opponents.iter().enumerate() {
+            opponent.params(&format!("{prefix}opp{rank}_"), params);
+        }
+    }
+
+    pub fn set_param(opponents: &mut [OpponentParams; 4], key: &str, value: f64) -> bool {
+        opponents.iter_mut().enumerate().any(|(rank, opponent)| {
+            key.strip_prefix(&format!("opp{rank}_"))
+                .is_some_and(|key| opponent.set_param(key, value))
+        })
+    }
+}
+
+/// Named `cont_<continent>_p<player>_<phase>`. Holding a continent is good for us and bad for
+/// everyone else.
+mod continents {
+    use enum_map::EnumMap;
+    use risk_shared::{map::Continent, player::PlayerId};
+    use tunable::{Bounds, Param};
+
+    use super::Eval;
+
+    fn name(continent: Continent, player: PlayerId) -> String {
+        format!("cont_{}_p{}", continent as u8, player as u8)
+    }
+
+    pub fn params(
+        continents: &EnumMap<Continent, EnumMap<PlayerId, Eval>>,
+        prefix: &str,
+        params: &mut Vec<Param>,
+    ) {
+        for (continent, players) in continents {
+            for (player, eval) in players {
+                let (min, max) = if player.is_p0() {
+                    (0.0, 10.0)
+                } else {
+                    (-10.0, 0.0)
+                };
+
+                for (suffix, value) in [("_0", eval.0), ("_1", eval.1)] {
+                    params.push(Param {
+                        name: format!("{prefix}{}{suffix}", name(continent, player)),
+                        value,
+                        bounds: Some(Bounds {
+                            min,
+                            max,
+                            c_end: 0.05,
+                            r_end: 0.01,
+                        }),
+                    });
                 }
             }
+        }
+    }
 
-            if weak {
-                weak_territories += 1;
-            }
+    pub fn set_param(
+        continents: &mut EnumMap<Continent, EnumMap<PlayerId, Eval>>,
+        key: &str,
+        value: f64,
+    ) -> bool {
+        for (continent, players) in continents {
+            for (player, eval) in players {
+                match key.strip_prefix(&name(continent, player)) {
+                    Some("_0") => eval.0 = value,
+                    Some("_1") => eval.1 = value,
+                    _ => continue,
+                }
 
-            if isolated {
-                isolated_territories += 1
+                return true;
             }
-        } else {
-            total_troops += troops;
         }
 
-        continent_territories_occupied[territory.continent()][game.occupier(territory)] += 1;
+        false
     }
+}
 
-    let expected_proportion = 1.0 / game.initial_players() as f64;
-    let expected_troops = total_troops as f64 * expected_proportion;
+impl Params {
+    /// Every `Eval`, in the same order as `Features::values`
+    pub fn evals(&self) -> impl Iterator<Item = Eval> + '_ {
+        [
+            self.bias,
+            self.territory_occupied,
+            self.weak_territory,
+            self.isolated_territory,
+            self.player_eliminated,
+            self.territory_conquered,
+            self.troop_count,
+            self.leader_elimination,
+        ]
+        .into_iter()
+        .chain(self.opponent_by_rank.iter().flat_map(OpponentParams::evals))
+        .chain(
+            self.continent_by_player
+                .values()
+                .flat_map(|x| x.values().copied()),
+        )
+    }
 
-    #[rustfmt::skip]
-    let mut score = params.bias;
-    score
-        .accum(territories_occupied as f64, params.territory_occupied)
-        .accum(weak_territories as f64, params.weak_territory)
-        .accum(isolated_territories as f64, params.isolated_territory)
-        .accum(
-            1.0 - (expected_troops / my_troops as f64).sqrt(),
-            params.troop_count,
+    pub fn evals_mut(&mut self) -> impl Iterator<Item = &mut Eval> {
+        [
+            &mut self.bias,
+            &mut self.territory_occupied,
+            &mut self.weak_territory,
+            &mut self.isolated_territory,
+            &mut self.player_eliminated,
+            &mut self.territory_conquered,
+            &mut self.troop_count,
+            &mut self.leader_elimination,
+        ]
+        .into_iter()
+        .chain(
+            self.opponent_by_rank
+                .iter_mut()
+                .flat_map(OpponentParams::evals_mut),
+        )
+        .chain(
+            self.continent_by_player
+                .values_mut()
+                .flat_map(|x| x.values_mut()),
         )
-        .accum(game.players_eliminated() as f64, params.player_eliminated);
+    }
+}
+
+/// The amount each `Eval` in `Params` is scaled by for a position, before the phase is resolved
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Features {
+    pub territory_occupied: f64,
+    pub weak_territory: f64,
+    pub isolated_territory: f64,
+    pub player_eliminated: f64,
+    pub territory_conquered: f64,
+    pub troop_count: f64,
+    pub leader_elimination: f64,
+    /// The territory share, troop share, income share and card count of each opponent, from the
+    /// strongest to the weakest
+    pub opponent_by_rank: [[f64; 4]; 4],
+    pub continent_by_player: EnumMap<Continent, EnumMap<PlayerId, f64>>,
+    pub card_sets_redeemed: u32,
+}
+
+impl Features {
+    pub fn new(game: &AttackGame) -> Self {
+        let mut territories_occupied = 0;
+        let mut continent_territories_occupied = EnumMap::from_fn(|_| EnumMap::from_fn(|_| 0));
+        let mut weak_territories = 0;
+        let mut isolated_territories = 0;
+        let mut my_troops = 0;
+        let mut total_troops = 0;
+        let mut territories_by_player = EnumMap::from_fn(|_| 0);
+        let mut troops_by_player = EnumMap::from_fn(|_| 0);
+
+        for territory in TerritoryId::ALL {
+            let troops = game.troops(territory);
+            if game.occupier(territory).is_p0() {
+                territories_occupied += 1;
+                my_troops += troops;
+                total_troops += troops;
+
+                let mut weak = false;
+                let mut isolated = true;
+                for &t in EDGES[territory] {
+                    if game.occupier(t).is_p0() {
+                        isolated = false;
+                    } else if troops == 1 {
+                        weak = true;
+                    }
+                }
+
+                if weak {
+                    weak_territories += 1;
+                }
+
+                if isolated {
+                    isolated_territories += 1
+                }
+            } else {
+                total_troops += troops;
+            }
+
+            continent_territories_occupied[territory.continent()][game.occupier(territory)] += 1;
+            territories_by_player[game.occupier(territory)] += 1;
+            troops_by_player[game.occupier(territory)] += troops;
+        }
+
+        let expected_proportion = 1.0 / game.initial_players() as f64;
+        let expected_troops = total_troops as f64 * expected_proportion;
+
+        let mut features = Self {
+            territory_occupied: territories_occupied as f64,
+            weak_territory: weak_territories as f64,
+            isolated_territory: isolated_territories as f64,
+            player_eliminated: game.players_eliminated() as f64,
+            territory_conquered: if game.territory_conquered() { 1.0 } else { 0.0 },
+            troop_count: 1.0 - (expected_troops / my_troops as f64).sqrt(),
+            leader_elimination: 0.0,
+            opponent_by_rank: [[0.0; 4]; 4],
+            continent_by_player: EnumMap::from_fn(|_| EnumMap::from_fn(|_| 0.0)),
+            card_sets_redeemed: game.card_sets_redeemed(),
+        };
 
-    for (continent, occupiers) in continent_territories_occupied {
-        if let Some((occupier, _)) = occupiers
+        let mut continent_bonus = EnumMap::from_fn(|_| 0);
+        for (continent, occupiers) in continent_territories_occupied {
+            if let Some((occupier, _)) = occupiers
+                .into_iter()
+                .find(|(_, count)| *count == continent.territory_count())
+            {
+                features.continent_by_player[continent][occupier] = 1.0;
+                continent_bonus[occupier] += continent.bonus();
+            }
+        }
+
+        let strength = |player: PlayerId| {
+            territories_by_player[player] as f64 / TerritoryId::ALL.len() as f64
+                + troops_by_player[player] as f64 / total_troops as f64
+        };
+
+        // Eliminated opponents have no strength, so they are ranked last with every feature at zero
+        let mut opponents = [PlayerId::P1, PlayerId::P2, PlayerId::P3, PlayerId::P4];
+        opponents.sort_unstable_by(|&x, &y| strength(y).total_cmp(&strength(x)));
+
+        let income = |player: PlayerId| {
+            std::cmp::max(3, territories_by_player[player] / 3) + continent_bonus[player]
+        };
+
+        let total_income = PlayerId::ALL
             .into_iter()
-            .find(|(_, count)| *count == continent.territory_count())
-        {
-            score.accum(1.0, params.continent_by_player[continent][occupier]);
+            .filter(|&player| territories_by_player[player] > 0)
+            .map(income)
+            .sum::<u32>();
+
+        for (features, &player) in features.opponent_by_rank.iter_mut().zip(&opponents) {
+            if territories_by_player[player] == 0 {
+                continue;
+            }
+
+            *features = [
+                territories_by_player[player] as f64 / TerritoryId::ALL.len() as f64,
+                troops_by_player[player] as f64 / total_troops as f64,
+                income(player) as f64 / total_income as f64,
+                game.card_count(player) as f64,
+            ];
         }
+
+        features.leader_elimination = game.players_eliminated() as f64
+            * f64::max(0.0, strength(opponents[0]) - strength(PlayerId::P0));
+
+        features
     }
 
-    if game.territory_conquered() {
-        score.accum(1.0, params.territory_conquered);
+    /// Every feature, in the same order as `Params::evals`
+    pub fn values(&self) -> impl Iterator<Item = f64> + '_ {
+        [
+            1.0,
+            self.territory_occupied,
+            self.weak_territory,
+            self.isolated_territory,
+            self.player_eliminated,
+            self.territory_conquered,
+            self.troop_count,
+            self.leader_elimination,
+        ]
+        .into_iter()
+        .chain(self.opponent_by_rank.iter().flatten().copied())
+        .chain(
+            self.continent_by_player
+                .values()
+                .flat_map(|x| x.values().copied()),
+        )
     }
 
-    1.0 / (1.0 + (-score.resolve(params.resolve_k, game.card_sets_redeemed())).exp())
+    /// The score before the phase is resolved and the sigmoid is applied
+    pub fn score(&self, params: &Params) -> Eval {
+        let mut score = Eval(0.0, 0.0);
+        for (value, eval) in self.values().zip(params.evals()) {
+            score.accum(value, eval);
+        }
+
+        score
+    }
+}
+
+pub fn evaluate(game: &AttackGame, params: &Params) -> f64 {
+    let features = Features::new(game);
+    let score = features
+        .score(params)
+        .resolve(params.resolve_k, features.card_sets_redeemed);
+
+    1.0 / (1.0 + (-score).exp())
 }
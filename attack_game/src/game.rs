@@ -4,8 +4,12 @@ use std::cmp;
 use enum_map::EnumMap;
 pub use mov::*;
 
+/// The number of cards that can be held after eliminating a player without having to redeem
+const MAX_CARDS: u32 = 6;
+
 use risk_shared::{
-    map::{TerritoryId, EDGES},
+    calculate_set_bonus,
+    map::{Continent, TerritoryId, EDGES},
     player::PlayerId,
 };
 
@@ -18,6 +22,8 @@ pub struct AttackGame {
     players_eliminated: u8,
     troops_to_place: u32,
     card_sets_redeemed: u32,
+    card_counts: EnumMap<PlayerId, u32>,
+    redemption_pending: bool,
 }
 
 impl Default for AttackGame {
@@ -36,6 +42,8 @@ impl AttackGame {
             troops_to_place: 0,
             players_eliminated: 0,
             card_sets_redeemed: 0,
+            card_counts: EnumMap::from_fn(|_| 0),
+            redemption_pending: false,
         }
     }
 
@@ -51,6 +59,16 @@ impl AttackGame {
 
         self.territory_conquered = false;
         self.players_eliminated = 0;
+        self.redemption_pending = false;
+    }
+
+    /// Sets the number of cards held by each player, using the same player ids as the occupiers
+    pub fn set_card_counts(&mut self, card_counts: EnumMap<PlayerId, u32>) {
+        self.card_counts = card_counts;
+    }
+
+    pub fn set_players_eliminated(&mut self, players_eliminated: u8) {
+        self.players_eliminated = players_eliminated;
     }
 
     pub fn set_troops_to_place(&mut self, troops: u32) {
@@ -60,10 +78,40 @@ impl AttackGame {
 
     pub fn make_move(&mut self, mov: Move) {
         match mov {
-            Move::Player(mov) => self.make_player_move(mov),
+            Move::Player(mov) => self.make_player_move(mov, self.max_dice(mov.origin)),
+            Move::PlayerDice(mov, dice) => self.make_player_move(mov, dice),
+            Move::EndAttack => {
+                assert!(self.turn.is_player());
+                self.turn = Turn::Fortify;
+            }
             Move::Chance(attackers_lost, defenders_lost) => {
                 self.make_chance_move(attackers_lost as u32, defenders_lost as u32)
             }
+            Move::MoveTroops(split) => {
+                let Turn::MoveTroops(mov, _) = self.turn else {
+                    unreachable!();
+                };
+
+                let troops_to_move = self.troops_after_attack(split);
+                self.troops[mov.origin] -= troops_to_move;
+                self.troops[mov.dest] = troops_to_move;
+                self.turn = Turn::Player;
+
+                if self.redemption_pending {
+                    self.redeem_captured_cards();
+                }
+            }
+            Move::Fortify(mov) => {
+                assert!(self.turn.is_fortify());
+                let troops_to_move = self.fortify_troops(mov);
+                self.troops[mov.origin] -= troops_to_move;
+                self.troops[mov.dest] += troops_to_move;
+                self.turn = Turn::Finished;
+            }
+            Move::FortifyPass => {
+                assert!(self.turn.is_fortify());
+                self.turn = Turn::Finished;
+            }
             Move::PlaceTroops(territory) => {
                 assert!(self.occupiers[territory] == PlayerId::P0);
                 let troop_count = 20_u32.clamp(self.troops_to_place / 5, self.troops_to_place);
@@ -77,13 +125,28 @@ impl AttackGame {
         }
     }
 
-    fn make_player_move(&mut self, mov: PlayerMove) {
+    fn make_player_move(&mut self, mov: PlayerMove, dice: u8) {
         assert!(self.turn.is_player());
-        self.turn = Turn::Chance(mov);
+        assert!(dice > 0 && u32::from(dice) < self.troops[mov.origin]);
+        self.turn = Turn::Chance(mov, dice);
+    }
+
+    /// The move that attacks with the given number of dice
+    pub fn attack_move(&self, mov: PlayerMove, dice: u8) -> Move {
+        if dice >= self.max_dice(mov.origin) {
+            Move::Player(mov)
+        } else {
+            Move::PlayerDice(mov, dice)
+        }
+    }
+
+    /// The maximum number of dice that can attack from a territory
+    pub fn max_dice(&self, origin: TerritoryId) -> u8 {
+        cmp::min(self.troops[origin] - 1, u32::from(MAX_DICE)) as u8
     }
 
     pub(crate) fn make_chance_move(&mut self, attackers_lost: u32, defenders_lost: u32) {
-        let Turn::Chance(mov) = self.turn else {
+        let Turn::Chance(mov, dice) = self.turn else {
             unreachable!()
         };
 
@@ -92,28 +155,157 @@ impl AttackGame {
         assert_eq!(self.occupiers[mov.origin], PlayerId::P0);
         assert_ne!(self.occupiers[mov.dest], PlayerId::P0);
 
+        let num_attackers = cmp::min(self.troops[mov.origin] - 1, u32::from(dice));
         self.troops[mov.origin] -= attackers_lost;
         self.troops[mov.dest] -= defenders_lost;
 
-        // Always move all troops when a territory is conquered
+        self.turn = Turn::Player;
+
         if self.troops[mov.dest] == 0 {
             self.territory_conquered = true;
 
-            let troops_to_move = self.troops[mov.origin] - 1;
-            assert_ne!(troops_to_move, 0);
-
-            self.troops[mov.origin] = 1;
-            self.troops[mov.dest] = troops_to_move;
-
             let last_occupier = self.occupiers[mov.dest];
             self.occupiers[mov.dest] = PlayerId::P0;
 
             if !self.occupiers.values().any(|&x| x == last_occupier) {
                 self.players_eliminated += 1;
+
+                // The cards of an eliminated player are captured, and too many cards must be
+                // redeemed once the troops have been moved
+                self.card_counts[PlayerId::P0] += self.card_counts[last_occupier];
+                self.card_counts[last_occupier] = 0;
+                self.redemption_pending =
+                    self.card_counts[PlayerId::P0] > MAX_CARDS && self.players_remaining() > 1;
             }
+
+            // At least as many troops as attacked must be moved into the conquered territory
+            assert!(self.troops[mov.origin] > 1);
+            self.turn = Turn::MoveTroops(mov, num_attackers as u8);
         }
+    }
 
-        self.turn = Turn::Player;
+    /// Redeems sets until fewer than 5 cards are held, and places the troops before attacking again.
+    /// The territories on the cards are unknown, so the matching territory bonus is ignored.
+    fn redeem_captured_cards(&mut self) {
+        self.redemption_pending = false;
+
+        let mut troops = 0;
+        while self.card_counts[PlayerId::P0] >= 5 {
+            troops += calculate_set_bonus(self.card_sets_redeemed);
+            self.card_sets_redeemed += 1;
+            self.card_counts[PlayerId::P0] -= 3;
+        }
+
+        if troops > 0 {
+            self.set_troops_to_place(troops);
+        }
+    }
+
+    /// Moves all troops into a conquered territory if there is one waiting for troops
+    pub(crate) fn move_all_troops(&mut self) {
+        if self.turn.is_move_troops() {
+            self.make_move(Move::MoveTroops(0));
+        }
+    }
+
+    /// The number of troops moved into the conquered territory for the given split
+    pub fn troops_after_attack(&self, split: u8) -> u32 {
+        let Turn::MoveTroops(mov, min_troops) = self.turn else {
+            unreachable!();
+        };
+
+        let available = self.troops[mov.origin] - 1;
+        let min_troops = cmp::min(u32::from(min_troops), available);
+        let (numerator, denominator) = TROOP_SPLITS[split as usize];
+
+        min_troops + (available - min_troops) * numerator / denominator
+    }
+
+    pub fn gen_troop_moves_into(&self, move_list: &mut Vec<u8>) {
+        move_list.clear();
+        let Turn::MoveTroops(mov, _) = self.turn else {
+            unreachable!();
+        };
+
+        move_list.push(0);
+
+        // Only leave troops behind if the attacking territory is still on the border
+        if EDGES[mov.origin].iter().all(|&t| self.occupiers[t].is_p0()) {
+            return;
+        }
+
+        for split in 1..TROOP_SPLITS.len() as u8 {
+            let troops = self.troops_after_attack(split);
+            if move_list
+                .iter()
+                .all(|&x| self.troops_after_attack(x) != troops)
+            {
+                move_list.push(split);
+            }
+        }
+    }
+
+    /// The number of troops moved by a fortify
+    pub fn fortify_troops(&self, mov: FortifyMove) -> u32 {
+        let (numerator, denominator) = FORTIFY_SPLITS[mov.split as usize];
+        (self.troops[mov.origin] - 1) * numerator / denominator
+    }
+
+    /// Generates fortifies to border territories that are connected to the origin through our
+    /// territories. Only border territories may leave troops behind.
+    pub fn gen_fortify_moves_into(&self, move_list: &mut Vec<FortifyMove>) {
+        move_list.clear();
+        assert_eq!(self.turn, Turn::Fortify);
+
+        let is_border = |t: TerritoryId| EDGES[t].iter().any(|&x| !self.occupiers[x].is_p0());
+
+        let mut component = EnumMap::from_fn(|_| usize::MAX);
+        let mut components = Vec::new();
+        for territory in TerritoryId::ALL {
+            if !self.occupiers[territory].is_p0() || component[territory] != usize::MAX {
+                continue;
+            }
+
+            let mut territories = vec![territory];
+            component[territory] = components.len();
+            let mut i = 0;
+            while let Some(&current) = territories.get(i) {
+                for &adjacent in EDGES[current] {
+                    if self.occupiers[adjacent].is_p0() && component[adjacent] == usize::MAX {
+                        component[adjacent] = components.len();
+                        territories.push(adjacent);
+                    }
+                }
+
+                i += 1;
+            }
+
+            components.push(territories);
+        }
+
+        for origin in TerritoryId::ALL
+            .into_iter()
+            .filter(|&t| self.occupiers[t].is_p0() && self.troops[t] > 1)
+        {
+            let splits = if is_border(origin) && self.troops[origin] > 2 {
+                FORTIFY_SPLITS.len() as u8
+            } else {
+                1
+            };
+
+            for &dest in components[component[origin]]
+                .iter()
+                .filter(|&&t| t != origin && is_border(t))
+            {
+                for split in 0..splits {
+                    move_list.push(FortifyMove {
+                        origin,
+                        dest,
+                        split,
+                    });
+                }
+            }
+        }
     }
 
     pub fn territories_occupied(&self) -> usize {
@@ -129,6 +321,18 @@ impl AttackGame {
         alive.values().filter(|&&x| x).count()
     }
 
+    /// The troops a player receives at the start of their turn, excluding cards
+    pub fn income(&self, player: PlayerId) -> u32 {
+        let territories = self.occupiers.values().filter(|&&x| x == player).count() as u32;
+        let continent_bonus = Continent::ALL
+            .into_iter()
+            .filter(|&c| c.iter_territories().all(|t| self.occupiers[t] == player))
+            .map(Continent::bonus)
+            .sum::<u32>();
+
+        cmp::max(3, territories / 3) + continent_bonus
+    }
+
     pub fn initial_players(&self) -> usize {
         self.players_remaining() + self.players_eliminated as usize
     }
@@ -153,7 +357,7 @@ impl AttackGame {
 
     pub fn gen_chance_moves_into(&self, move_list: &mut Vec<(u8, u8)>) {
         move_list.clear();
-        let Turn::Chance(mov) = self.turn else {
+        let Turn::Chance(mov, dice) = self.turn else {
             unreachable!();
         };
 
@@ -163,7 +367,7 @@ impl AttackGame {
         assert_eq!(self.occupiers[mov.origin], PlayerId::P0);
         assert_ne!(self.occupiers[mov.dest], PlayerId::P0);
 
-        let num_attackers = cmp::min(origin_troops - 1, 3);
+        let num_attackers = cmp::min(origin_troops - 1, u32::from(dice));
         let num_defenders = cmp::min(dest_troops, 2);
         let battles = cmp::min(num_attackers, num_defenders);
 
@@ -221,6 +425,17 @@ impl AttackGame {
         self.troops[territory] -= count;
     }
 
+    pub(crate) fn set_territory(
+        &mut self,
+        territory: TerritoryId,
+        occupier: PlayerId,
+        troops: u32,
+    ) {
+        assert!(troops > 0);
+        self.occupiers[territory] = occupier;
+        self.troops[territory] = troops;
+    }
+
     pub fn occupier(&self, territory: TerritoryId) -> PlayerId {
         self.occupiers[territory]
     }
@@ -237,6 +452,10 @@ impl AttackGame {
         self.card_sets_redeemed
     }
 
+    pub fn card_count(&self, player: PlayerId) -> u32 {
+        self.card_counts[player]
+    }
+
     pub fn troops_to_place(&self) -> u32 {
         self.troops_to_place
     }
@@ -245,8 +464,15 @@ impl AttackGame {
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum Turn {
     Player,
-    Chance(PlayerMove),
+    /// An attack with the given number of dice is waiting to be resolved
+    Chance(PlayerMove, u8),
+    /// Troops must be moved into a conquered territory, with at least the given number of troops
+    MoveTroops(PlayerMove, u8),
     PlaceTroops,
+    /// We have stopped attacking, and may fortify
+    Fortify,
+    /// The turn is over
+    Finished,
 }
 
 impl Turn {
@@ -266,6 +492,14 @@ impl Turn {
         matches!(self, Self::Chance(..))
     }
 
+    /// Returns `true` if the turn is [`MoveTroops`].
+    ///
+    /// [`MoveTroops`]: Turn::MoveTroops
+    #[must_use]
+    pub fn is_move_troops(&self) -> bool {
+        matches!(self, Self::MoveTroops(..))
+    }
+
     /// Returns `true` if the turn is [`PlaceTroops`].
     ///
     /// [`PlaceTroops`]: Turn::PlaceTroops
@@ -273,6 +507,22 @@ impl Turn {
     pub fn is_place_troops(&self) -> bool {
         matches!(self, Self::PlaceTroops)
     }
+
+    /// Returns `true` if the turn is [`Fortify`].
+    ///
+    /// [`Fortify`]: Turn::Fortify
+    #[must_use]
+    pub fn is_fortify(&self) -> bool {
+        matches!(self, Self::Fortify)
+    }
+
+    /// Returns `true` if the turn is [`Finished`].
+    ///
+    /// [`Finished`]: Turn::Finished
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Finished)
+    }
 }
 
 #[cfg(target_family = "wasm")]
@@ -1,7 +1,7 @@
 use risk_shared::map::TerritoryId;
 
-/// For now, we always move the maximum number of troops possible
-/// and assume that the opponent defends the same way
+/// An attack from one territory to another. The number of dice is part of the [`Move`], and we
+/// assume that the opponent always defends with the maximum number of dice possible
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 #[repr(C)]
 pub struct PlayerMove {
@@ -15,21 +15,49 @@ fn _size_check() {
     }
 }
 
+/// Moves troops between two connected territories at the end of the turn, using the split from
+/// [`FORTIFY_SPLITS`]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct FortifyMove {
+    pub origin: TerritoryId,
+    pub dest: TerritoryId,
+    pub split: u8,
+}
+
 #[repr(align(4))]
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum Move {
+    /// Attacks with the maximum number of dice possible
     Player(PlayerMove),
+    /// Attacks with fewer dice than the maximum
+    PlayerDice(PlayerMove, u8),
+    /// Stops attacking for the rest of the turn
+    EndAttack,
     Chance(u8, u8),
     PlaceTroops(TerritoryId),
+    /// Moves troops into a conquered territory, using the split from [`TROOP_SPLITS`]
+    MoveTroops(u8),
+    Fortify(FortifyMove),
+    FortifyPass,
 }
 
+pub const MAX_DICE: u8 = 3;
+
+/// The fractions of the optional troops that can be moved into a conquered territory, in
+/// addition to the troops that attacked. The first split always moves every troop.
+pub const TROOP_SPLITS: [(u32, u32); 3] = [(1, 1), (1, 2), (0, 1)];
+
+/// The fractions of the movable troops that can be fortified
+pub const FORTIFY_SPLITS: [(u32, u32); 2] = [(1, 1), (1, 2)];
+
 impl Move {
-    /// Returns `true` if the move is [`Player`].
+    /// Returns `true` if the move is [`Player`] or [`PlayerDice`].
     ///
     /// [`Player`]: Move::Player
+    /// [`PlayerDice`]: Move::PlayerDice
     #[must_use]
     pub fn is_player(&self) -> bool {
-        matches!(self, Self::Player(..))
+        matches!(self, Self::Player(..) | Self::PlayerDice(..))
     }
 
     /// Returns `true` if the move is [`Chance`].
@@ -1,3 +1,8 @@
+pub mod battle;
+pub mod cards;
+pub mod draft;
 pub mod evaluate;
 pub mod game;
+pub mod nn;
+pub mod placement;
 pub mod strategy;
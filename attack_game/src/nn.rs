@@ -0,0 +1,244 @@
+use std::sync::OnceLock;
+
+use risk_shared::map::TerritoryId;
+
+use crate::game::AttackGame;
+
+pub const INPUTS: usize = 128;
+pub const HIDDEN: usize = 32;
+/// The value logit, followed by the log strength of each territory
+pub const OUTPUTS: usize = 1 + TerritoryId::ALL.len();
+
+/// Inputs and hidden activations are quantized with these scales, so both are clipped to just
+/// under 4
+pub const INPUT_SCALE: f32 = 32.0;
+pub const HIDDEN_SCALE: f32 = 32.0;
+pub const MAX_ACTIVATION: f32 = 127.0 / HIDDEN_SCALE;
+
+const MAGIC: &[u8; 4] = b"RBNN";
+const VERSION: u32 = 1;
+
+static EMBEDDED: &[u8] = include_bytes!("nn.bin");
+static NETWORK: OnceLock<Network> = OnceLock::new();
+
+/// The network embedded in the binary
+pub fn network() -> &'static Network {
+    NETWORK.get_or_init(|| Network::from_bytes(EMBEDDED).expect("invalid embedded network"))
+}
+
+/// The network inputs for the position, from the perspective of P0
+pub fn inputs(game: &AttackGame) -> [f32; INPUTS] {
+    let mut inputs = [0.0; INPUTS];
+    for (i, territory) in TerritoryId::ALL.into_iter().enumerate() {
+        let troops = (game.troops(territory) as f32).ln_1p();
+        if game.occupier(territory).is_p0() {
+            inputs[3 * i] = 1.0;
+            inputs[3 * i + 1] = troops;
+        } else {
+            inputs[3 * i + 2] = troops;
+        }
+    }
+
+    let i = 3 * TerritoryId::ALL.len();
+    inputs[i] = f32::min(1.0, game.card_sets_redeemed() as f32 / 10.0);
+    inputs[i + 1] = f32::from(game.players_eliminated());
+    inputs
+}
+
+/// Unquantized weights, with each layer stored as one row per output
+#[derive(Clone, PartialEq, Debug)]
+pub struct FloatWeights {
+    pub w1: Vec<f32>,
+    pub b1: Vec<f32>,
+    pub w2: Vec<f32>,
+    pub b2: Vec<f32>,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Output {
+    logits: [f32; OUTPUTS],
+}
+
+impl Output {
+    /// The probability of winning
+    pub fn value(&self) -> f64 {
+        1.0 / (1.0 + (-f64::from(self.logits[0])).exp())
+    }
+
+    /// The predicted ratio of our troops to enemy troops around the territory
+    pub fn strength(&self, territory: TerritoryId) -> f64 {
+        f64::from(self.logits[1 + territory as usize]).exp()
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Network {
+    w1: Vec<i8>,
+    w1_scale: f32,
+    b1: Vec<f32>,
+    w2: Vec<i8>,
+    w2_scale: f32,
+    b2: Vec<f32>,
+}
+
+impl Network {
+    pub fn quantize(weights: &FloatWeights) -> Self {
+        assert_eq!(weights.w1.len(), INPUTS * HIDDEN);
+        assert_eq!(weights.b1.len(), HIDDEN);
+        assert_eq!(weights.w2.len(), HIDDEN * OUTPUTS);
+        assert_eq!(weights.b2.len(), OUTPUTS);
+
+        let (w1, w1_scale) = quantize_layer(&weights.w1);
+        let (w2, w2_scale) = quantize_layer(&weights.w2);
+        Self {
+            w1,
+            w1_scale,
+            b1: weights.b1.clone(),
+            w2,
+            w2_scale,
+            b2: weights.b2.clone(),
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut reader = Reader(bytes);
+        if reader.take(4)? != MAGIC || reader.u32()? != VERSION {
+            return None;
+        }
+
+        let dimensions = [reader.u32()?, reader.u32()?, reader.u32()?];
+        if dimensions != [INPUTS as u32, HIDDEN as u32, OUTPUTS as u32] {
+            return None;
+        }
+
+        let w1_scale = reader.f32()?;
+        let w1 = reader.i8s(INPUTS * HIDDEN)?;
+        let b1 = reader.f32s(HIDDEN)?;
+        let w2_scale = reader.f32()?;
+        let w2 = reader.i8s(HIDDEN * OUTPUTS)?;
+        let b2 = reader.f32s(OUTPUTS)?;
+
+        reader.0.is_empty().then_some(Self {
+            w1,
+            w1_scale,
+            b1,
+            w2,
+            w2_scale,
+            b2,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        for x in [VERSION, INPUTS as u32, HIDDEN as u32, OUTPUTS as u32] {
+            bytes.extend(x.to_le_bytes());
+        }
+
+        bytes.extend(self.w1_scale.to_le_bytes());
+        bytes.extend(self.w1.iter().map(|&x| x as u8));
+        bytes.extend(self.b1.iter().flat_map(|x| x.to_le_bytes()));
+        bytes.extend(self.w2_scale.to_le_bytes());
+        bytes.extend(self.w2.iter().map(|&x| x as u8));
+        bytes.extend(self.b2.iter().flat_map(|x| x.to_le_bytes()));
+        bytes
+    }
+
+    pub fn forward(&self, game: &AttackGame) -> Output {
+        let inputs = inputs(game).map(|x| quantize(x, INPUT_SCALE));
+
+        let mut hidden = [0; HIDDEN];
+        for (i, (weights, bias)) in self.w1.chunks_exact(INPUTS).zip(&self.b1).enumerate() {
+            let x = dot(weights, &inputs) as f32 * self.w1_scale / INPUT_SCALE + bias;
+            hidden[i] = quantize(x.clamp(0.0, MAX_ACTIVATION), HIDDEN_SCALE);
+        }
+
+        let mut logits = [0.0; OUTPUTS];
+        for (i, (weights, bias)) in self.w2.chunks_exact(HIDDEN).zip(&self.b2).enumerate() {
+            logits[i] = dot(weights, &hidden) as f32 * self.w2_scale / HIDDEN_SCALE + bias;
+        }
+
+        Output { logits }
+    }
+}
+
+fn quantize(x: f32, scale: f32) -> i8 {
+    (x * scale).round().clamp(-127.0, 127.0) as i8
+}
+
+fn quantize_layer(weights: &[f32]) -> (Vec<i8>, f32) {
+    let max = weights.iter().fold(0.0, |max, x| f32::max(max, x.abs()));
+    let scale = if max == 0.0 { 1.0 } else { max / 127.0 };
+    (
+        weights
+            .iter()
+            .map(|&x| quantize(x, scale.recip()))
+            .collect(),
+        scale,
+    )
+}
+
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+fn dot(x: &[i8], y: &[i8]) -> i32 {
+    use std::arch::wasm32::*;
+
+    debug_assert_eq!(x.len(), y.len());
+    debug_assert_eq!(x.len() % 16, 0);
+
+    let mut total = i32x4_splat(0);
+    for (x, y) in x.chunks_exact(16).zip(y.chunks_exact(16)) {
+        // SAFETY: both chunks are 16 bytes long, and v128_load has no alignment requirement
+        let (x, y) = unsafe {
+            (
+                v128_load(x.as_ptr().cast::<v128>()),
+                v128_load(y.as_ptr().cast::<v128>()),
+            )
+        };
+
+        let low = i32x4_dot_i16x8(i16x8_extend_low_i8x16(x), i16x8_extend_low_i8x16(y));
+        let high = i32x4_dot_i16x8(i16x8_extend_high_i8x16(x), i16x8_extend_high_i8x16(y));
+        total = i32x4_add(total, i32x4_add(low, high));
+    }
+
+    i32x4_extract_lane::<0>(total)
+        + i32x4_extract_lane::<1>(total)
+        + i32x4_extract_lane::<2>(total)
+        + i32x4_extract_lane::<3>(total)
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_feature = "simd128")))]
+fn dot(x: &[i8], y: &[i8]) -> i32 {
+    x.iter()
+        .zip(y)
+        .map(|(&x, &y)| i32::from(x) * i32::from(y))
+        .sum()
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Option<&'a [u8]> {
+        if self.0.len() < count {
+            return None;
+        }
+
+        let (bytes, rest) = self.0.split_at(count);
+        self.0 = rest;
+        Some(bytes)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn f32(&mut self) -> Option<f32> {
+        Some(f32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn f32s(&mut self, count: usize) -> Option<Vec<f32>> {
+        (0..count).map(|_| self.f32()).collect()
+    }
+
+    fn i8s(&mut self, count: usize) -> Option<Vec<i8>> {
+        Some(self.take(count)?.iter().map(|&x| x as i8).collect())
+    }
+}
@@ -0,0 +1,62 @@
+use enum_map::EnumMap;
+use risk_shared::{
+    map::{TerritoryId, EDGES},
+    player::PlayerId,
+};
+
+use crate::game::AttackGame;
+
+/// Places the initial troops the other players have remaining, to estimate the board at the start
+/// of our first turn. Troops are placed one at a time in turn order from the player after us, the
+/// same way the engine interleaves them, and each one reinforces the territory that is most
+/// outnumbered by our neighbouring troops. The last `players_before_us` players also place their
+/// first turn income, since they move before we do. Our own remaining troops are not placed.
+pub fn project_initial_troops(
+    game: &mut AttackGame,
+    mut troops_remaining: EnumMap<PlayerId, u32>,
+    players_before_us: usize,
+) {
+    let players = game.initial_players();
+    troops_remaining[PlayerId::P0] = 0;
+
+    while troops_remaining.values().any(|&x| x > 0) {
+        for player in PlayerId::ALL.into_iter().take(players).skip(1) {
+            if troops_remaining[player] == 0 {
+                continue;
+            }
+
+            troops_remaining[player] -= 1;
+            if let Some(territory) = most_threatened(game, player) {
+                game.set_territory(territory, player, game.troops(territory) + 1);
+            }
+        }
+    }
+
+    for player in PlayerId::ALL
+        .into_iter()
+        .take(players)
+        .skip(players - players_before_us)
+    {
+        if let Some(territory) = most_threatened(game, player) {
+            let income = game.income(player);
+            game.set_territory(territory, player, game.troops(territory) + income);
+        }
+    }
+}
+
+fn most_threatened(game: &AttackGame, player: PlayerId) -> Option<TerritoryId> {
+    TerritoryId::ALL
+        .into_iter()
+        .filter(|&t| game.occupier(t) == player)
+        .filter_map(|t| {
+            let threat = EDGES[t]
+                .iter()
+                .filter(|&&x| game.occupier(x).is_p0())
+                .map(|&x| game.troops(x))
+                .max()?;
+
+            Some((t, i64::from(threat) - i64::from(game.troops(t))))
+        })
+        .max_by_key(|&(_, threat)| threat)
+        .map(|(t, _)| t)
+}
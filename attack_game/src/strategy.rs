@@ -1,7 +1,9 @@
+use mcts::gumbel::SequentialHalving;
 use risk_shared::player::PlayerId;
 
 use crate::game::{AttackGame, Move, PlayerMove};
 
+pub mod opponent;
 pub mod puct;
 pub mod state;
 
@@ -15,9 +17,17 @@ pub trait StrategyExt: mcts::Strategy<Action = Move> {
 
     fn root_game(&self) -> &AttackGame;
     fn evaluate(&self, game: &AttackGame) -> f64;
+    /// Scores stopping the attack at `game` in the same way as the end of a playout, so that it
+    /// can be compared with the scores of the moves
+    fn stand_pat(&self, game: &AttackGame) -> f64;
 
     fn simulation_rounds(&self) -> u64;
     fn reset_simulation_rounds(&self);
+
+    /// The root selection policy, or `None` to select the most visited child
+    fn root_selection(&self) -> Option<SequentialHalving>;
+    fn prior(data: &Self::Data) -> f64;
+    fn sample_gumbel(&mut self, count: usize) -> Vec<f64>;
 }
 
 const PROBABILITIES: [[WeightedAlias; 2]; 3] = [
@@ -61,13 +71,13 @@ const CHANCE_MOVES: [[[Move; 8]; 2]; 3] = const {
     moves
 };
 
-fn resolve_chance(game: &AttackGame, mov: PlayerMove, rng: &mut impl rand::Rng) -> Move {
+fn resolve_chance(game: &AttackGame, mov: PlayerMove, dice: u8, rng: &mut impl rand::Rng) -> Move {
     assert_eq!(game.occupier(mov.origin), PlayerId::P0);
     assert_ne!(game.occupier(mov.dest), PlayerId::P0);
     let origin_troops: usize = game.troops(mov.origin).try_into().unwrap();
     let dest_troops = game.troops(mov.dest).try_into().unwrap();
 
-    let num_attackers = std::cmp::min(origin_troops - 1, 3);
+    let num_attackers = std::cmp::min(origin_troops - 1, dice as usize);
     let num_defenders = std::cmp::min(dest_troops, 2);
     let result = PROBABILITIES[num_attackers - 1][num_defenders - 1].sample(rng);
     CHANCE_MOVES[num_attackers - 1][num_defenders - 1][result]
@@ -0,0 +1,59 @@
+use risk_shared::{
+    map::{TerritoryId, EDGES},
+    player::PlayerId,
+};
+
+use crate::game::AttackGame;
+
+use super::puct::resolve_battle;
+
+/// The number of troops an opponent must have in excess of ours before it attacks
+const ATTACK_MARGIN: u32 = 2;
+
+/// Plays one round of turns for the opponents that border us, in turn order. Each opponent
+/// reinforces its largest territory next to us, and then keeps attacking us wherever it has a
+/// clear advantage. Battles between opponents are ignored.
+pub fn simulate_round(game: &mut AttackGame, rng: &mut impl rand::Rng) {
+    for player in PlayerId::ALL.into_iter().skip(1) {
+        let Some(stack) = TerritoryId::ALL
+            .into_iter()
+            .filter(|&t| {
+                game.occupier(t) == player && EDGES[t].iter().any(|&x| game.occupier(x).is_p0())
+            })
+            .max_by_key(|&t| game.troops(t))
+        else {
+            continue;
+        };
+
+        let income = game.income(player);
+        game.set_territory(stack, player, game.troops(stack) + income);
+
+        while let Some((origin, dest)) = best_attack(game, player) {
+            let (attackers_lost, defenders_lost) =
+                resolve_battle(game.troops(origin), game.troops(dest), rng);
+
+            game.remove_troops(origin, attackers_lost);
+            if defenders_lost == game.troops(dest) {
+                let troops = game.troops(origin) - 1;
+                game.remove_troops(origin, troops);
+                game.set_territory(dest, player, troops);
+            } else {
+                game.remove_troops(dest, defenders_lost);
+            }
+        }
+    }
+}
+
+fn best_attack(game: &AttackGame, player: PlayerId) -> Option<(TerritoryId, TerritoryId)> {
+    TerritoryId::ALL
+        .into_iter()
+        .filter(|&t| game.occupier(t) == player)
+        .flat_map(|origin| {
+            EDGES[origin]
+                .iter()
+                .filter(|&&t| game.occupier(t).is_p0())
+                .map(move |&dest| (origin, dest))
+        })
+        .filter(|&(origin, dest)| game.troops(origin) > game.troops(dest) + ATTACK_MARGIN)
+        .max_by_key(|&(origin, dest)| game.troops(origin) - game.troops(dest))
+}
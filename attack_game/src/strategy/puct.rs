@@ -1,16 +1,26 @@
 use std::cell::Cell;
 
 use crate::{
-    evaluate,
-    game::{retain_different_dest, retain_different_origin, AttackGame, Move, PlayerMove, Turn},
+    battle, evaluate,
+    game::{
+        retain_different_dest, retain_different_origin, AttackGame, Move, PlayerMove, Turn,
+        MAX_DICE,
+    },
+    nn,
 };
 
-use super::{resolve_chance, StrategyExt};
+use super::{opponent, resolve_chance, StrategyExt};
 
+use mcts::gumbel::{self, SequentialHalving};
+use mcts::multiplayer::{self, Backup};
 use mcts::uct::a0puct as puct;
 use rand::prelude::SliceRandom;
 use risk_shared::map::{TerritoryId, EDGES};
 
+const MAX_FORTIFY_MOVES: usize = 16;
+/// The number of simulated opponent rounds averaged when scoring the position at the root
+const STAND_PAT_ROUNDS: u32 = 16;
+
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub struct Data {
     pub prediction: f64,
@@ -22,6 +32,21 @@ pub struct Params {
     pub c_puct_troops: f64,
     pub first_enemy_troop_reduction: f64,
     pub first_friendly_troop_reduction: f64,
+    pub troop_split_prediction: f64,
+    /// The prior of attacking with fewer dice, relative to attacking with the maximum. Attacks
+    /// with fewer dice are not searched if this is zero.
+    pub reduced_dice_prediction: f64,
+    /// The prior of stopping the attack. Stopping is not searched if this is zero.
+    pub stop_prediction: f64,
+    /// The prior of fortifying rather than passing. The fortify is not searched if this is zero.
+    pub fortify_prediction: f64,
+    /// Whether to play out a round of opponent turns before evaluating
+    pub simulate_opponents: bool,
+    /// Whether to evaluate positions with the embedded network rather than `eval`
+    pub network_eval: bool,
+    /// Whether to predict territory strengths with the embedded network for the priors
+    pub network_policy: bool,
+    pub gumbel: Option<SequentialHalving>,
     pub eval: evaluate::Params,
 }
 
@@ -32,17 +57,44 @@ impl Default for Params {
             c_puct_troops: 0.702745173702057,
             first_enemy_troop_reduction: 0.13864357808607813,
             first_friendly_troop_reduction: 0.5888177056325355,
+            troop_split_prediction: 0.2,
+            reduced_dice_prediction: 0.0,
+            stop_prediction: 0.0,
+            fortify_prediction: 0.0,
+            simulate_opponents: false,
+            network_eval: false,
+            network_policy: false,
+            gumbel: None,
             eval: evaluate::Params::default(),
         }
     }
 }
 
+tunable::tunable! {
+    impl Tunable for Params {
+        c_puct: float(0.0, 10.0, 0.05, 0.01),
+        c_puct_troops: float(0.0, 10.0, 0.05, 0.01),
+        first_enemy_troop_reduction as "puct_first_enemy_troop_reduction": float(0.0, 1.0, 0.05, 0.01),
+        first_friendly_troop_reduction as "puct_first_friendly_troop_reduction": float(0.0, 1.0, 0.05, 0.01),
+        troop_split_prediction: float(0.0, 0.5, 0.02, 0.01),
+        reduced_dice_prediction: float(0.0, 0.5, 0.02, 0.01),
+        stop_prediction: float(0.0, 0.5, 0.02, 0.01),
+        fortify_prediction: float(0.0, 1.0, 0.05, 0.01),
+        simulate_opponents: fixed,
+        network_eval: fixed,
+        network_policy: fixed,
+        gumbel: fixed,
+        eval: nested(""),
+    }
+}
+
 pub struct AttackPUCT<R: rand::Rng> {
     params: Params,
     root_game: AttackGame,
     game: AttackGame,
     rng: R,
     player_moves: Vec<PlayerMove>, // Cache this vec
+    player_moves_stale: bool,
     simulation_rounds: Cell<u64>,
 }
 
@@ -64,6 +116,7 @@ impl<R: rand::Rng + Clone> StrategyExt for AttackPUCT<R> {
             game,
             rng,
             player_moves: Vec::new(),
+            player_moves_stale: false,
             simulation_rounds: Cell::new(0),
         }
     }
@@ -77,7 +130,23 @@ impl<R: rand::Rng + Clone> StrategyExt for AttackPUCT<R> {
     }
 
     fn evaluate(&self, game: &AttackGame) -> f64 {
-        evaluate::evaluate(game, &self.params.eval)
+        self.eval(game)
+    }
+
+    fn stand_pat(&self, game: &AttackGame) -> f64 {
+        if !self.params.simulate_opponents {
+            return self.eval(game);
+        }
+
+        let mut rng = self.rng.clone();
+        let total = (0..STAND_PAT_ROUNDS)
+            .map(|_| {
+                let mut game = game.clone();
+                opponent::simulate_round(&mut game, &mut rng);
+                self.eval_after_round(&game)
+            })
+            .sum::<f64>();
+        total / f64::from(STAND_PAT_ROUNDS)
     }
 
     fn simulation_rounds(&self) -> u64 {
@@ -87,6 +156,20 @@ impl<R: rand::Rng + Clone> StrategyExt for AttackPUCT<R> {
     fn reset_simulation_rounds(&self) {
         self.simulation_rounds.set(0)
     }
+
+    fn root_selection(&self) -> Option<SequentialHalving> {
+        self.params.gumbel
+    }
+
+    fn prior(data: &Data) -> f64 {
+        data.prediction
+    }
+
+    fn sample_gumbel(&mut self, count: usize) -> Vec<f64> {
+        (0..count)
+            .map(|_| gumbel::gumbel(self.rng.gen_range(f64::EPSILON..1.0)))
+            .collect()
+    }
 }
 
 impl<R: rand::Rng + Clone> mcts::Strategy for AttackPUCT<R> {
@@ -102,18 +185,22 @@ impl<R: rand::Rng + Clone> mcts::Strategy for AttackPUCT<R> {
         self.reset();
     }
 
+    fn make_move(&mut self, action: &Self::Action) {
+        self.game.make_move(*action);
+    }
+
     fn select(&mut self, node: &mcts::Node<Self::Action, Self::Data>) -> mcts::Selection {
         match self.game.turn() {
-            Turn::Player | Turn::PlaceTroops => {
-                let c_puct = if self.game.turn().is_player() {
-                    self.params.c_puct
-                } else {
+            Turn::Player | Turn::MoveTroops(..) | Turn::PlaceTroops | Turn::Fortify => {
+                let c_puct = if self.game.turn().is_place_troops() {
                     self.params.c_puct_troops
+                } else {
+                    self.params.c_puct
                 };
 
                 if node.children.is_empty() {
                     assert_ne!(self.game.turn(), Turn::PlaceTroops);
-                    mcts::Selection::Terminal(self.evaluate(&self.game))
+                    mcts::Selection::Terminal(self.evaluate_leaf())
                 } else {
                     let selection = (0..node.children.len())
                         .fold((f64::NEG_INFINITY, 0), |(max, max_index), i| {
@@ -139,8 +226,8 @@ impl<R: rand::Rng + Clone> mcts::Strategy for AttackPUCT<R> {
                     mcts::Selection::Selection(selection as u32)
                 }
             }
-            Turn::Chance(mov) => {
-                let mov = resolve_chance(&self.game, mov, &mut self.rng);
+            Turn::Chance(mov, dice) => {
+                let mov = resolve_chance(&self.game, mov, dice, &mut self.rng);
                 let mov_index = node
                     .children
                     .iter()
@@ -150,6 +237,7 @@ impl<R: rand::Rng + Clone> mcts::Strategy for AttackPUCT<R> {
 
                 mcts::Selection::Selection(mov_index as u32)
             }
+            Turn::Finished => mcts::Selection::Terminal(self.evaluate_leaf()),
         }
     }
 
@@ -159,32 +247,53 @@ impl<R: rand::Rng + Clone> mcts::Strategy for AttackPUCT<R> {
     ) -> Vec<(Self::Action, Self::Data)> {
         match self.game.turn() {
             Turn::Player => {
-                let mut actions = vec![];
-                self.game.gen_player_moves_into(&mut actions);
-                actions.shuffle(&mut self.rng);
-                let mut actions = actions
-                    .into_iter()
-                    .map(|mov| {
-                        let data = Data {
-                            prediction: self.predictor(&self.game, mov).sqrt(),
-                        };
+                let mut moves = vec![];
+                self.game.gen_player_moves_into(&mut moves);
+                moves.shuffle(&mut self.rng);
+
+                let policy = self.policy(&self.game);
+                let mut actions = Vec::with_capacity(moves.len());
+                for mov in moves {
+                    let prediction = self.predictor(&self.game, policy.as_ref(), mov).sqrt();
+                    actions.push((Move::Player(mov), Data { prediction }));
+
+                    if self.params.reduced_dice_prediction > 0.0 {
+                        for dice in 1..self.game.max_dice(mov.origin) {
+                            let data = Data {
+                                prediction: prediction * self.params.reduced_dice_prediction,
+                            };
+
+                            actions.push((Move::PlayerDice(mov, dice), data));
+                        }
+                    }
+                }
 
-                        (Move::Player(mov), data)
-                    })
-                    .collect::<Vec<_>>();
+                if actions.is_empty() {
+                    // The fortify is the only decision left in the turn
+                    if self.params.fortify_prediction > 0.0 {
+                        actions.push((Move::EndAttack, Data { prediction: 1.0 }));
+                    }
 
-                let scale = actions
-                    .iter()
-                    .map(|(_, x)| x.prediction)
-                    .sum::<f64>()
-                    .recip();
+                    return actions;
+                }
+
+                let scale = (1.0 - self.params.stop_prediction)
+                    / actions.iter().map(|(_, x)| x.prediction).sum::<f64>();
                 for (_, data) in &mut actions {
                     data.prediction *= scale;
                 }
 
+                if self.params.stop_prediction > 0.0 {
+                    let data = Data {
+                        prediction: self.params.stop_prediction,
+                    };
+
+                    actions.push((Move::EndAttack, data));
+                }
+
                 actions
             }
-            Turn::Chance(_) => {
+            Turn::Chance(..) => {
                 let mut actions = vec![];
                 self.game.gen_chance_moves_into(&mut actions);
                 actions
@@ -192,8 +301,28 @@ impl<R: rand::Rng + Clone> mcts::Strategy for AttackPUCT<R> {
                     .map(|(x, y)| (Move::Chance(x, y), Data { prediction: 0.0 }))
                     .collect()
             }
+            Turn::MoveTroops(..) => {
+                let mut splits = vec![];
+                self.game.gen_troop_moves_into(&mut splits);
+
+                let split_prediction = self.params.troop_split_prediction;
+                let move_all_prediction = 1.0 - split_prediction * (splits.len() - 1) as f64;
+                splits
+                    .into_iter()
+                    .map(|split| {
+                        let prediction = if split == 0 {
+                            move_all_prediction
+                        } else {
+                            split_prediction
+                        };
+
+                        (Move::MoveTroops(split), Data { prediction })
+                    })
+                    .collect()
+            }
             Turn::PlaceTroops => {
                 // Consider only placing on border territories
+                let policy = self.policy(&self.game);
                 let mut actions = TerritoryId::ALL
                     .into_iter()
                     .filter(|&x| {
@@ -202,7 +331,9 @@ impl<R: rand::Rng + Clone> mcts::Strategy for AttackPUCT<R> {
                     })
                     .map(|x| {
                         let data = Data {
-                            prediction: self.troops_predictor(&self.game, x).sqrt(),
+                            prediction: self
+                                .troops_predictor(&self.game, policy.as_ref(), x)
+                                .sqrt(),
                         };
 
                         (Move::PlaceTroops(x), data)
@@ -220,34 +351,82 @@ impl<R: rand::Rng + Clone> mcts::Strategy for AttackPUCT<R> {
 
                 actions
             }
+            Turn::Fortify => {
+                if self.params.fortify_prediction <= 0.0 {
+                    return vec![];
+                }
+
+                let mut moves = vec![];
+                self.game.gen_fortify_moves_into(&mut moves);
+                let policy = self.policy(&self.game);
+
+                let mut actions = moves
+                    .into_iter()
+                    .map(|mov| {
+                        let troops = self.game.fortify_troops(mov) as f64;
+                        let data = Data {
+                            prediction: (troops
+                                * self.troops_predictor(&self.game, policy.as_ref(), mov.dest))
+                            .sqrt(),
+                        };
+
+                        (Move::Fortify(mov), data)
+                    })
+                    .collect::<Vec<_>>();
+
+                // Consider only the most promising fortifies
+                actions.sort_by(|(_, x), (_, y)| y.prediction.total_cmp(&x.prediction));
+                actions.truncate(MAX_FORTIFY_MOVES);
+
+                let scale = self.params.fortify_prediction
+                    / actions.iter().map(|(_, x)| x.prediction).sum::<f64>();
+                for (_, data) in &mut actions {
+                    data.prediction *= scale;
+                }
+
+                let pass_prediction = if actions.is_empty() {
+                    1.0
+                } else {
+                    1.0 - self.params.fortify_prediction
+                };
+
+                actions.push((
+                    Move::FortifyPass,
+                    Data {
+                        prediction: pass_prediction,
+                    },
+                ));
+
+                actions
+            }
+            Turn::Finished => vec![],
         }
     }
 
     fn simulate(&mut self, _: &mcts::Node<Self::Action, Self::Data>) -> f64 {
-        if self.game.turn().is_place_troops() {
-            let moves = TerritoryId::ALL
-                .into_iter()
-                .filter(|&x| {
-                    self.game.occupier(x).is_p0()
-                        && EDGES[x].iter().any(|&x| !self.game.occupier(x).is_p0())
-                })
-                .collect::<Vec<_>>();
-
-            while self.game.turn().is_place_troops() {
-                self.game
-                    .make_move(Move::PlaceTroops(*moves.choose(&mut self.rng).unwrap()))
-            }
-        }
+        self.place_troops_randomly();
 
-        if let Turn::Chance(mov) = self.game.turn() {
-            let mov = resolve_chance(&self.game, mov, &mut self.rng);
+        if let Turn::Chance(mov, dice) = self.game.turn() {
+            let mov = resolve_chance(&self.game, mov, dice, &mut self.rng);
             self.game.make_move(mov);
         }
 
+        self.move_all_troops();
+
         let stand_pat = self.evaluate(&self.game);
+        let stand_pat_game = self.params.simulate_opponents.then(|| self.game.clone());
+        if self.game.turn().is_fortify() || self.game.turn().is_finished() {
+            return self.finish_simulation(stand_pat, stand_pat_game);
+        }
 
         self.game.gen_player_moves_into(&mut self.player_moves);
+        self.player_moves_stale = false;
         loop {
+            // Troops placed from captured cards can start new attacks anywhere
+            if std::mem::take(&mut self.player_moves_stale) {
+                self.game.gen_player_moves_into(&mut self.player_moves);
+            }
+
             match self.player_moves.len() {
                 0 => break,
                 1 => {
@@ -256,7 +435,7 @@ impl<R: rand::Rng + Clone> mcts::Strategy for AttackPUCT<R> {
                     if self.game.troops(player_move.origin) > 10
                         && self.game.troops(player_move.dest) > 10
                     {
-                        let (attackers_lost, defenders_lost) = approx_resolve_chance(
+                        let (attackers_lost, defenders_lost) = resolve_battle(
                             self.game.troops(player_move.origin),
                             self.game.troops(player_move.dest),
                             &mut self.rng,
@@ -264,15 +443,32 @@ impl<R: rand::Rng + Clone> mcts::Strategy for AttackPUCT<R> {
 
                         self.game.make_move(Move::Player(player_move));
                         self.game.make_chance_move(attackers_lost, defenders_lost);
+                        self.move_all_troops();
                         self.simulation_rounds.set(self.simulation_rounds.get() + 1);
                     } else {
                         while self.game.troops(player_move.origin) > 1 {
                             self.game.make_move(Move::Player(player_move));
 
                             let chance_move =
-                                resolve_chance(&self.game, player_move, &mut self.rng);
+                                resolve_chance(&self.game, player_move, MAX_DICE, &mut self.rng);
                             self.game.make_move(chance_move);
+                            self.move_all_troops();
                             self.simulation_rounds.set(self.simulation_rounds.get() + 2);
+
+                            // Stop the attack once it has turned bad, if stopping is allowed
+                            if self.params.stop_prediction > 0.0
+                                && !self.game.occupier(player_move.dest).is_p0()
+                                && self.game.troops(player_move.origin)
+                                    <= self.game.troops(player_move.dest)
+                            {
+                                break;
+                            }
+                        }
+
+                        if !self.game.occupier(player_move.dest).is_p0()
+                            && self.game.troops(player_move.origin) > 1
+                        {
+                            break;
                         }
                     }
 
@@ -312,7 +508,7 @@ impl<R: rand::Rng + Clone> mcts::Strategy for AttackPUCT<R> {
                             .map(|(troops, _)| troops)
                             .sum::<u32>();
 
-                        let (mut attackers_lost, defenders_lost) = approx_resolve_chance(
+                        let (mut attackers_lost, defenders_lost) = resolve_battle(
                             total_troops,
                             self.game.troops(player_move.dest),
                             &mut self.rng,
@@ -337,6 +533,7 @@ impl<R: rand::Rng + Clone> mcts::Strategy for AttackPUCT<R> {
 
                         self.game.make_move(mov);
                         self.game.make_chance_move(1, defenders_lost);
+                        self.move_all_troops();
 
                         // Incremental gen move
                         for (_, territory) in available_troops
@@ -367,9 +564,10 @@ impl<R: rand::Rng + Clone> mcts::Strategy for AttackPUCT<R> {
                         // Keep attacking the same target if we have more troops than they do
                         loop {
                             let chance_move =
-                                resolve_chance(&self.game, player_move, &mut self.rng);
+                                resolve_chance(&self.game, player_move, MAX_DICE, &mut self.rng);
                             self.game.make_move(Move::Player(player_move));
                             self.game.make_move(chance_move);
+                            self.move_all_troops();
                             self.simulation_rounds.set(self.simulation_rounds.get() + 1);
 
                             // If we only have one troop left, than than we cannot have more than
@@ -389,58 +587,156 @@ impl<R: rand::Rng + Clone> mcts::Strategy for AttackPUCT<R> {
             }
         }
 
-        f64::max(stand_pat, self.evaluate(&self.game))
+        self.finish_simulation(stand_pat, stand_pat_game)
     }
 
     fn backpropagate(
         &mut self,
         score: f64,
-        mut tree: mcts::TreeWalker<'_, '_, Self::Action, Self::Data>,
+        tree: mcts::TreeWalker<'_, '_, Self::Action, Self::Data>,
     ) {
-        while let Some(node) = tree.pop() {
-            node.visits += 1;
-            node.score += score;
-        }
+        // Only P0 ever moves, so every node is scored from our perspective
+        let rewards = multiplayer::rewards_from_score(0, score, self.game.initial_players());
+        multiplayer::backpropagate(&rewards, Backup::Paranoid(0), tree);
     }
 }
 
 impl<R: rand::Rng> AttackPUCT<R> {
-    fn predictor(&self, game: &AttackGame, mov: PlayerMove) -> f64 {
-        self.territory_strength(game, mov.dest)
+    fn place_troops_randomly(&mut self) {
+        if !self.game.turn().is_place_troops() {
+            return;
+        }
+
+        let moves = TerritoryId::ALL
+            .into_iter()
+            .filter(|&x| {
+                self.game.occupier(x).is_p0()
+                    && EDGES[x].iter().any(|&x| !self.game.occupier(x).is_p0())
+            })
+            .collect::<Vec<_>>();
+
+        while self.game.turn().is_place_troops() {
+            self.game
+                .make_move(Move::PlaceTroops(*moves.choose(&mut self.rng).unwrap()))
+        }
     }
 
-    fn troops_predictor(&self, game: &AttackGame, territory: TerritoryId) -> f64 {
-        self.territory_strength(game, territory).recip()
+    /// Moves all troops into a conquered territory, and places the troops from any cards that
+    /// had to be redeemed
+    fn move_all_troops(&mut self) {
+        self.game.move_all_troops();
+        if self.game.turn().is_place_troops() {
+            self.place_troops_randomly();
+            self.player_moves_stale = true;
+        }
     }
 
-    #[inline]
-    fn territory_strength(&self, game: &AttackGame, territory: TerritoryId) -> f64 {
-        let mut defenders = 0.0;
-        let mut attackers = 0.0;
-        if game.occupier(territory).is_p0() {
-            attackers = game.troops(territory) as f64 - self.params.first_friendly_troop_reduction;
+    /// Scores the better of stopping at the stand pat position and the end of the playout, after
+    /// the opponents have responded if they are simulated
+    fn finish_simulation(&mut self, stand_pat: f64, stand_pat_game: Option<AttackGame>) -> f64 {
+        let score = self.eval(&self.game);
+        let Some(stand_pat_game) = stand_pat_game else {
+            return f64::max(stand_pat, score);
+        };
+
+        if stand_pat > score {
+            self.game = stand_pat_game;
+        }
+
+        opponent::simulate_round(&mut self.game, &mut self.rng);
+        self.simulation_rounds.set(self.simulation_rounds.get() + 1);
+        self.eval_after_round(&self.game)
+    }
+
+    /// Scores a position that the search cannot continue from in the same way as the end of a
+    /// playout, so that these positions do not avoid the simulated opponents
+    fn evaluate_leaf(&mut self) -> f64 {
+        let stand_pat = self.eval(&self.game);
+        let stand_pat_game = self.params.simulate_opponents.then(|| self.game.clone());
+        self.finish_simulation(stand_pat, stand_pat_game)
+    }
+
+    fn eval_after_round(&self, game: &AttackGame) -> f64 {
+        if game.territories_occupied() == 0 {
+            0.0
         } else {
-            defenders = game.troops(territory) as f64 - self.params.first_enemy_troop_reduction;
+            self.eval(game)
         }
+    }
 
-        for &territory in EDGES[territory] {
-            let troops = game.troops(territory);
-            if game.occupier(territory).is_p0() {
-                attackers += troops as f64 - self.params.first_friendly_troop_reduction;
-            } else {
-                defenders += troops as f64 - self.params.first_enemy_troop_reduction;
-            }
+    fn eval(&self, game: &AttackGame) -> f64 {
+        if self.params.network_eval {
+            nn::network().forward(game).value()
+        } else {
+            evaluate::evaluate(game, &self.params.eval)
         }
+    }
+
+    fn policy(&self, game: &AttackGame) -> Option<nn::Output> {
+        self.params
+            .network_policy
+            .then(|| nn::network().forward(game))
+    }
+
+    fn predictor(&self, game: &AttackGame, policy: Option<&nn::Output>, mov: PlayerMove) -> f64 {
+        match policy {
+            Some(policy) => policy.strength(mov.dest),
+            None => territory_strength(game, mov.dest, &self.params),
+        }
+    }
 
-        attackers / defenders
+    fn troops_predictor(
+        &self,
+        game: &AttackGame,
+        policy: Option<&nn::Output>,
+        territory: TerritoryId,
+    ) -> f64 {
+        match policy {
+            Some(policy) => policy.strength(territory).recip(),
+            None => territory_strength(game, territory, &self.params).recip(),
+        }
     }
 }
 
+/// The ratio of our troops to enemy troops in and around the territory
+#[inline]
+pub fn territory_strength(game: &AttackGame, territory: TerritoryId, params: &Params) -> f64 {
+    let mut defenders = 0.0;
+    let mut attackers = 0.0;
+    if game.occupier(territory).is_p0() {
+        attackers = game.troops(territory) as f64 - params.first_friendly_troop_reduction;
+    } else {
+        defenders = game.troops(territory) as f64 - params.first_enemy_troop_reduction;
+    }
+
+    for &territory in EDGES[territory] {
+        let troops = game.troops(territory);
+        if game.occupier(territory).is_p0() {
+            attackers += troops as f64 - params.first_friendly_troop_reduction;
+        } else {
+            defenders += troops as f64 - params.first_enemy_troop_reduction;
+        }
+    }
+
+    attackers / defenders
+}
+
 fn fast_choose(moves: &[PlayerMove], rng: &mut impl rand::Rng) -> PlayerMove {
     let value = u64::from(rng.next_u32()) * moves.len() as u64;
     moves[(value >> 32) as usize]
 }
 
+/// Resolves a full battle, using the exact odds when they are available
+pub(super) fn resolve_battle(
+    attacking_troops: u32,
+    defending_troops: u32,
+    rng: &mut impl rand::Rng,
+) -> (u32, u32) {
+    battle::odds()
+        .sample(attacking_troops - 1, defending_troops, rng)
+        .unwrap_or_else(|| approx_resolve_chance(attacking_troops, defending_troops, rng))
+}
+
 fn approx_resolve_chance(
     attacking_troops: u32,
     defending_troops: u32,
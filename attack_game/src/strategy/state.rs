@@ -2,7 +2,7 @@ use enum_map::EnumMap;
 use risk_shared::{map::TerritoryId, player::PlayerId};
 
 use super::StrategyExt;
-use crate::game::{AttackGame, Move, PlayerMove};
+use crate::game::{AttackGame, FortifyMove, Move, PlayerMove, Turn};
 
 pub trait StatefulStrategy {
     type Rng: rand::RngCore;
@@ -18,6 +18,7 @@ pub trait StatefulStrategy {
         troops: EnumMap<TerritoryId, u32>,
         occupiers: EnumMap<TerritoryId, PlayerId>,
         card_sets_redeemed: u32,
+        card_counts: EnumMap<PlayerId, u32>,
     );
 
     fn place_troops(
@@ -27,11 +28,22 @@ pub trait StatefulStrategy {
         occupiers: EnumMap<TerritoryId, PlayerId>,
         distributions: &mut EnumMap<TerritoryId, u32>,
         card_sets_redeemed: u32,
+        card_counts: EnumMap<PlayerId, u32>,
     );
 
-    fn get_move(&mut self) -> Option<(PlayerMove, bool)>;
+    /// The next attack and the number of dice to attack with, and whether it should be repeated
+    /// until the attack is over. Returns `None` if we should stop attacking.
+    fn get_move(&mut self) -> Option<(PlayerMove, u8, bool)>;
 
-    fn make_moves(&mut self, player: PlayerMove, chance: (u8, u8));
+    /// The number of troops to move into the territory that was just conquered, or `None` if the
+    /// search is not waiting for troops to be moved
+    fn get_troops_after_attack(&mut self) -> Option<u32>;
+
+    /// The fortify at the end of the turn, where `Some(None)` passes. Returns `None` if the fortify
+    /// is not searched.
+    fn get_fortify(&mut self) -> Option<Option<(FortifyMove, u32)>>;
+
+    fn make_moves(&mut self, player: PlayerMove, dice: u8, chance: (u8, u8));
 }
 
 #[repr(transparent)]
@@ -65,9 +77,11 @@ impl<'a, S: StrategyExt> StatefulStrategy for State<'a, S> {
         troops: EnumMap<TerritoryId, u32>,
         occupiers: EnumMap<TerritoryId, PlayerId>,
         card_sets_redeemed: u32,
+        card_counts: EnumMap<PlayerId, u32>,
     ) {
         let mut game = AttackGame::new();
         game.set_state(troops, occupiers, card_sets_redeemed);
+        game.set_card_counts(card_counts);
 
         self.mcts = mcts::MCTS::new(self.mcts.strategy().create_from(game));
     }
@@ -79,9 +93,11 @@ impl<'a, S: StrategyExt> StatefulStrategy for State<'a, S> {
         occupiers: EnumMap<TerritoryId, PlayerId>,
         distributions: &mut EnumMap<TerritoryId, u32>,
         card_sets_redeemed: u32,
+        card_counts: EnumMap<PlayerId, u32>,
     ) {
         let mut game = AttackGame::new();
         game.set_state(troops, occupiers, card_sets_redeemed);
+        game.set_card_counts(card_counts);
         game.set_troops_to_place(troop_count);
 
         self.mcts = mcts::MCTS::new(self.mcts.strategy().create_from(game));
@@ -89,7 +105,8 @@ impl<'a, S: StrategyExt> StatefulStrategy for State<'a, S> {
         while self.mcts.strategy().root_game().turn().is_place_troops() {
             let nodes = self.calculate_nodes();
             let nodes = (nodes as f64 * f64::max(1.0, (troop_count as f64).ln())) as u32;
-            let Move::PlaceTroops(territory) = self.calculate_move(nodes) else {
+            // There is always a territory to place troops on
+            let Some(Move::PlaceTroops(territory)) = self.calculate_move(nodes) else {
                 unreachable!();
             };
 
@@ -101,17 +118,17 @@ impl<'a, S: StrategyExt> StatefulStrategy for State<'a, S> {
         }
     }
 
-    fn get_move(&mut self) -> Option<(PlayerMove, bool)> {
+    fn get_move(&mut self) -> Option<(PlayerMove, u8, bool)> {
         if self.mcts.root().children.is_empty() {
             return None;
         }
 
         let nodes = self.calculate_nodes();
-        let mov = self.calculate_move(nodes);
+        let mov = self.calculate_move(nodes)?;
         let eval = self
             .mcts
             .strategy()
-            .evaluate(self.mcts.strategy().root_game());
+            .stand_pat(self.mcts.strategy().root_game());
 
         if self
             .mcts
@@ -124,8 +141,11 @@ impl<'a, S: StrategyExt> StatefulStrategy for State<'a, S> {
             return None;
         }
 
-        let Move::Player(mov) = mov else {
-            unreachable!()
+        let (mov, dice) = match mov {
+            Move::Player(mov) => (mov, self.mcts.strategy().root_game().max_dice(mov.origin)),
+            Move::PlayerDice(mov, dice) => (mov, dice),
+            Move::EndAttack => return None,
+            _ => unreachable!(),
         };
 
         assert!(self.mcts.strategy().root_game().troops(mov.origin) > 1);
@@ -139,15 +159,73 @@ impl<'a, S: StrategyExt> StatefulStrategy for State<'a, S> {
         );
 
         let repeat = self.mcts.root().children.len() == 1;
-        Some((mov, repeat))
+        Some((mov, dice, repeat))
     }
 
-    fn make_moves(&mut self, player: PlayerMove, chance: (u8, u8)) {
-        self.mcts.move_root(Move::Player(player));
+    fn get_troops_after_attack(&mut self) -> Option<u32> {
+        if !self.mcts.strategy().root_game().turn().is_move_troops() {
+            return None;
+        }
+
+        let mov = if self.mcts.root().children.len() == 1 {
+            self.mcts.root().children[0].0
+        } else {
+            let nodes = self.calculate_nodes();
+            self.calculate_move(nodes)?
+        };
+
+        let Move::MoveTroops(split) = mov else {
+            unreachable!();
+        };
+
+        let troops = self.mcts.strategy().root_game().troops_after_attack(split);
+
+        self.mcts.move_root(mov);
+        Some(troops)
+    }
+
+    fn get_fortify(&mut self) -> Option<Option<(FortifyMove, u32)>> {
+        match self.mcts.strategy().root_game().turn() {
+            Turn::Player => self.mcts.move_root(Move::EndAttack),
+            Turn::Fortify => (),
+            _ => return None,
+        }
+
+        if self.mcts.root().children.is_empty() {
+            return None;
+        }
+
+        let mov = if self.mcts.root().children.len() == 1 {
+            self.mcts.root().children[0].0
+        } else {
+            let nodes = self.calculate_nodes();
+            self.calculate_move(nodes)?
+        };
+
+        let mov = match mov {
+            Move::Fortify(mov) => {
+                let troops = self.mcts.strategy().root_game().fortify_troops(mov);
+                Some((mov, troops))
+            }
+            Move::FortifyPass => None,
+            _ => unreachable!(),
+        };
+
+        Some(mov)
+    }
+
+    fn make_moves(&mut self, player: PlayerMove, dice: u8, chance: (u8, u8)) {
+        let mov = self.mcts.strategy().root_game().attack_move(player, dice);
+        self.mcts.move_root(mov);
         self.mcts.move_root(Move::Chance(chance.0, chance.1));
     }
 }
 
+/// Whether a search has simulated enough rounds to stop, once it has added a few nodes
+fn out_of_rounds<S: StrategyExt>(strategy: &S, nodes: u32) -> bool {
+    nodes > 20 && strategy.simulation_rounds() >= 10000
+}
+
 impl<'a, S: StrategyExt> State<'a, S> {
     fn calculate_nodes(&self) -> u32 {
         let game = self.mcts.strategy().root_game();
@@ -156,8 +234,19 @@ impl<'a, S: StrategyExt> State<'a, S> {
             .min(25 * (TerritoryId::ALL.len() - game.territories_occupied()) as u32)
     }
 
-    fn calculate_move(&mut self, nodes: u32) -> Move {
+    /// The move with the best search result, or `None` if the root has no moves
+    fn calculate_move(&mut self, nodes: u32) -> Option<Move> {
         self.mcts.strategy().reset_simulation_rounds();
+
+        if let Some(root_selection) = self.mcts.strategy().root_selection() {
+            let children = self.mcts.root().children.len();
+            let gumbel = self.mcts.strategy_mut().sample_gumbel(children);
+            let index =
+                root_selection.search(&mut self.mcts, nodes, &gumbel, S::prior, out_of_rounds)?;
+
+            return Some(self.mcts.root().children[index].0);
+        }
+
         let mut next_check = 0;
         for i in 0..nodes {
             if i >= next_check {
@@ -170,14 +259,14 @@ impl<'a, S: StrategyExt> State<'a, S> {
                 }
             }
 
-            if i > 20 && self.mcts.strategy().simulation_rounds() >= 10000 {
+            if out_of_rounds(self.mcts.strategy(), i) {
                 break;
             }
 
             self.mcts.add_node();
         }
 
-        *self.mcts.most_visits().unwrap().0
+        self.mcts.most_visits().map(|x| *x.0)
     }
 
     fn top_two_visits(&self) -> Option<(u32, u32)> {
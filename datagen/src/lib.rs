@@ -0,0 +1,141 @@
+use std::sync::{Arc, Mutex};
+
+use attack_game::game::AttackGame;
+use enum_map::EnumMap;
+use rand::Rng;
+use risk_bots::strategy::{encode_card_counts, encode_occupiers};
+use risk_helper::{state::ClientState, ManagedPlayer};
+use risk_shared::{
+    map::TerritoryId,
+    player::PlayerId,
+    query::Query,
+    record::{
+        Cause, MoveAttack, MoveDefend, MoveDistributeTroops, MoveFortify, MoveRedeemCards,
+        MoveTroopsAfterAttack,
+    },
+};
+
+/// A position at the start of an attack phase, as seen by `evaluate::evaluate`
+#[derive(Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Sample {
+    pub troops: Vec<u32>,
+    /// Player ids based on turn order, where 0 is the player to move
+    pub occupiers: Vec<u8>,
+    pub card_sets_redeemed: u32,
+    pub players_eliminated: u8,
+    pub card_counts: Vec<u32>,
+    /// 1 if the player to move went on to win the game, and 0 otherwise
+    pub outcome: f64,
+}
+
+impl Sample {
+    pub fn from_state(state: &ClientState) -> Self {
+        let occupiers = encode_occupiers(state);
+        Self {
+            troops: TerritoryId::ALL
+                .into_iter()
+                .map(|t| state.territories()[t].troops)
+                .collect(),
+            occupiers: TerritoryId::ALL
+                .into_iter()
+                .map(|t| occupiers[t] as u8)
+                .collect(),
+            card_sets_redeemed: state.card_sets_redeemed(),
+            players_eliminated: 0,
+            card_counts: encode_card_counts(state).into_values().collect(),
+            outcome: 0.0,
+        }
+    }
+
+    pub fn game(&self) -> AttackGame {
+        let mut game = AttackGame::new();
+        game.set_state(
+            EnumMap::from_fn(|t: TerritoryId| self.troops[t as usize]),
+            EnumMap::from_fn(|t: TerritoryId| PlayerId::n(self.occupiers[t as usize]).unwrap()),
+            self.card_sets_redeemed,
+        );
+        game.set_players_eliminated(self.players_eliminated);
+        game.set_card_counts(EnumMap::from_fn(|p: PlayerId| self.card_counts[p as usize]));
+        game
+    }
+}
+
+/// The samples recorded during a game, along with the real id of the player to move
+pub type Samples = Arc<Mutex<Vec<(PlayerId, Sample)>>>;
+
+/// Wraps a player, and records the position at the start of some of its attack phases
+pub struct Recorder<P: ManagedPlayer> {
+    player: P,
+    samples: Samples,
+    sample_rate: f64,
+    turn_started: bool,
+}
+
+impl<P: ManagedPlayer> Recorder<P> {
+    pub fn new(player: P, samples: Samples, sample_rate: f64) -> Self {
+        Self {
+            player,
+            samples,
+            sample_rate,
+            turn_started: false,
+        }
+    }
+}
+
+impl<P: ManagedPlayer> ManagedPlayer for Recorder<P> {
+    fn reset(&mut self) {
+        self.turn_started = false;
+        self.player.reset();
+    }
+
+    fn pre_query(&mut self, state: &ClientState, query: &Query) {
+        self.player.pre_query(state, query);
+    }
+
+    fn query_attack(&mut self, state: &ClientState) -> Option<MoveAttack> {
+        if std::mem::take(&mut self.turn_started) && rand::thread_rng().gen_bool(self.sample_rate) {
+            let sample = Sample::from_state(state);
+            self.samples.lock().unwrap().push((state.me().id, sample));
+        }
+
+        self.player.query_attack(state)
+    }
+
+    fn query_claim_territory(&mut self, state: &ClientState) -> TerritoryId {
+        self.player.query_claim_territory(state)
+    }
+
+    fn query_defend(&mut self, state: &ClientState, move_attack_id: usize) -> MoveDefend {
+        self.player.query_defend(state, move_attack_id)
+    }
+
+    fn query_distribute_troops(
+        &mut self,
+        state: &ClientState,
+        cause: Cause,
+    ) -> MoveDistributeTroops {
+        self.turn_started |= cause.is_turn_started();
+        self.player.query_distribute_troops(state, cause)
+    }
+
+    fn query_fortify(&mut self, state: &ClientState) -> Option<MoveFortify> {
+        self.player.query_fortify(state)
+    }
+
+    fn query_place_initial_troop(&mut self, state: &ClientState) -> TerritoryId {
+        self.player.query_place_initial_troop(state)
+    }
+
+    fn query_redeem_cards(&mut self, state: &ClientState, cause: Cause) -> MoveRedeemCards {
+        self.player.query_redeem_cards(state, cause)
+    }
+
+    fn query_troops_after_attack(
+        &mut self,
+        state: &ClientState,
+        record_attack_id: usize,
+    ) -> MoveTroopsAfterAttack {
+        self.player
+            .query_troops_after_attack(state, record_attack_id)
+    }
+}
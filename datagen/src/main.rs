@@ -0,0 +1,106 @@
+use std::{
+    fs::OpenOptions,
+    io::{BufWriter, Write},
+    sync::{Arc, Mutex},
+};
+
+use datagen::{Recorder, Sample, Samples};
+use enum_map::EnumMap;
+use rand::{RngCore, SeedableRng};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use risk_bots::{
+    complex::ComplexExample, strategy::PuctBot, very_bad::VeryBad, very_bad13::VeryBad13,
+};
+use risk_engine::{
+    game_engine::{GameEngine, GameResult},
+    player::PlayerConnection,
+};
+use risk_helper::ManagedPlayerBot;
+use risk_shared::player::{PlayerBot, PlayerId};
+
+const GAMES: usize = 10000;
+const BATCH_SIZE: usize = 64;
+const SAMPLE_RATE: f64 = 0.2;
+const OUTPUT_FILE: &str = "data.jsonl";
+
+fn main() {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(OUTPUT_FILE)
+        .unwrap();
+    let mut writer = BufWriter::new(file);
+
+    let mut total_samples = 0;
+    for batch in 0..GAMES.div_ceil(BATCH_SIZE) {
+        let samples = (0..BATCH_SIZE)
+            .into_par_iter()
+            .map(|_| play_game())
+            .flatten()
+            .collect::<Vec<_>>();
+
+        for sample in &samples {
+            serde_json::to_writer(&mut writer, sample).unwrap();
+            writeln!(writer).unwrap();
+        }
+
+        writer.flush().unwrap();
+        total_samples += samples.len();
+        println!(
+            "{} Games: {} Samples",
+            (batch + 1) * BATCH_SIZE,
+            total_samples
+        );
+    }
+}
+
+/// Plays one game and returns its samples, which are empty if the game was cancelled
+fn play_game() -> Vec<Sample> {
+    let samples = Samples::default();
+    let players = EnumMap::from_fn(|player| {
+        let rng = rand_xoshiro::Xoshiro256StarStar::seed_from_u64(rand::thread_rng().next_u64());
+        let samples = Arc::clone(&samples);
+        let bot = match player {
+            PlayerId::P0 | PlayerId::P1 => Box::new(ManagedPlayerBot::new(Recorder::new(
+                PuctBot::new(rng),
+                samples,
+                SAMPLE_RATE,
+            ))) as Box<dyn PlayerBot>,
+            PlayerId::P2 => Box::new(ManagedPlayerBot::new(Recorder::new(
+                VeryBad::new(),
+                samples,
+                SAMPLE_RATE,
+            ))),
+            PlayerId::P3 => Box::new(ManagedPlayerBot::new(Recorder::new(
+                VeryBad13::new(),
+                samples,
+                SAMPLE_RATE,
+            ))),
+            PlayerId::P4 => Box::new(ManagedPlayerBot::new(Recorder::new(
+                ComplexExample::new(rng),
+                samples,
+                SAMPLE_RATE,
+            ))),
+        };
+        PlayerConnection::new(bot, player)
+    });
+
+    let mut game = GameEngine::new(players);
+    let GameResult::Success(winner) = game.start() else {
+        return Vec::new();
+    };
+
+    drop(game);
+    let samples = Arc::try_unwrap(samples)
+        .map(Mutex::into_inner)
+        .unwrap()
+        .unwrap();
+
+    samples
+        .into_iter()
+        .map(|(player, mut sample)| {
+            sample.outcome = if player == winner { 1.0 } else { 0.0 };
+            sample
+        })
+        .collect()
+}
@@ -0,0 +1,123 @@
+use crate::{Strategy, MCTS};
+
+/// Root action selection using Gumbel-Top-k sampling and sequential halving, as in
+/// "Policy improvement by planning with Gumbel" (Danihelka et al.). This makes much better use
+/// of small simulation budgets than selecting the most visited child.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SequentialHalving {
+    /// The maximum number of actions sampled at the root
+    pub k: usize,
+    pub c_visit: f64,
+    pub c_scale: f64,
+}
+
+impl Default for SequentialHalving {
+    fn default() -> Self {
+        Self {
+            k: 16,
+            c_visit: 50.0,
+            c_scale: 1.0,
+        }
+    }
+}
+
+impl SequentialHalving {
+    /// Searches the root using at most `budget` nodes, and returns the index of the selected root
+    /// child, or `None` if the root has no children. `gumbel` must contain one sample for each
+    /// root child. The search also ends early once `stop` returns true, which is given the number
+    /// of nodes added so far.
+    pub fn search<S: Strategy>(
+        &self,
+        mcts: &mut MCTS<'_, S>,
+        budget: u32,
+        gumbel: &[f64],
+        prior: impl Fn(&S::Data) -> f64,
+        stop: impl Fn(&S, u32) -> bool,
+    ) -> Option<usize> {
+        let num_children = mcts.root().children.len();
+        assert_eq!(gumbel.len(), num_children);
+
+        let priors = mcts
+            .root()
+            .children
+            .iter()
+            .map(|(_, data, _)| prior(data).max(f64::MIN_POSITIVE))
+            .collect::<Vec<_>>();
+        let logits = priors.iter().map(|x| x.ln()).collect::<Vec<_>>();
+
+        let mut candidates = (0..num_children).collect::<Vec<_>>();
+        candidates
+            .sort_by(|&x, &y| f64::total_cmp(&(gumbel[y] + logits[y]), &(gumbel[x] + logits[x])));
+        candidates.truncate(self.k.max(1));
+
+        if candidates.len() <= 1 {
+            return candidates.first().copied();
+        }
+
+        let phases = candidates.len().next_power_of_two().ilog2();
+        let mut nodes = 0;
+        let mut finished = false;
+        while candidates.len() > 1 {
+            let visits = std::cmp::max(1, budget / (phases * candidates.len() as u32));
+            'phase: for &candidate in &candidates {
+                for _ in 0..visits {
+                    if nodes >= budget || stop(mcts.strategy(), nodes) {
+                        finished = true;
+                        break 'phase;
+                    }
+
+                    mcts.add_node_at(candidate);
+                    nodes += 1;
+                }
+            }
+
+            let children = &mcts.root().children;
+            let max_visits = children
+                .iter()
+                .filter_map(|(_, _, node)| node.as_ref().map(|x| x.visits))
+                .max()
+                .unwrap_or(0);
+
+            let q = |index: usize| {
+                children[index]
+                    .2
+                    .as_ref()
+                    .filter(|x| x.visits > 0)
+                    .map(|x| x.win_ratio())
+            };
+
+            // Unvisited children are completed with the prior-weighted mean of the visited ones,
+            // rather than being scored as a loss
+            let (weighted_q, visited_prior) = (0..num_children)
+                .filter_map(|i| q(i).map(|q| (priors[i] * q, priors[i])))
+                .fold((0.0, 0.0), |x, y| (x.0 + y.0, x.1 + y.1));
+            let mixed_value = if visited_prior > 0.0 {
+                weighted_q / visited_prior
+            } else {
+                0.0
+            };
+
+            let score = |index: usize| {
+                gumbel[index]
+                    + logits[index]
+                    + (self.c_visit + f64::from(max_visits))
+                        * self.c_scale
+                        * q(index).unwrap_or(mixed_value)
+            };
+
+            candidates.sort_by(|&x, &y| f64::total_cmp(&score(y), &score(x)));
+            candidates.truncate(candidates.len().div_ceil(2));
+
+            if finished {
+                break;
+            }
+        }
+
+        candidates.first().copied()
+    }
+}
+
+/// Transforms a uniform sample in (0, 1) into a sample from the standard Gumbel distribution
+pub fn gumbel(uniform: f64) -> f64 {
+    -(-uniform.ln()).ln()
+}
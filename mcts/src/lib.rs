@@ -1,5 +1,7 @@
 use typed_arena::Arena;
 
+pub mod gumbel;
+pub mod multiplayer;
 pub mod uct;
 
 pub trait Strategy {
@@ -12,6 +14,9 @@ pub trait Strategy {
 
     fn select(&mut self, node: &Node<Self::Action, Self::Data>) -> Selection;
 
+    /// Plays an action from the current position, as if it had been returned from `select`
+    fn make_move(&mut self, action: &Self::Action);
+
     fn expand(&mut self, node: &Node<Self::Action, Self::Data>) -> Vec<(Self::Action, Self::Data)>;
 
     fn simulate(&mut self, node: &Node<Self::Action, Self::Data>) -> f64;
@@ -34,6 +39,12 @@ pub trait Strategy {
     ) -> Vec<(Self::Action, Self::Data)> {
         self.expand(node)
     }
+
+    /// The player to move in the current position. This is recorded on every node when it is
+    /// created, and is only needed for games with more than two players.
+    fn player_to_move(&self) -> u8 {
+        0
+    }
 }
 
 pub enum Selection {
@@ -47,6 +58,8 @@ pub struct Node<'a, Action, Data> {
     pub visits: u32,
     /// The score for this node from the perspective of the parent
     pub score: f64,
+    /// The player to move at this node
+    pub player: u8,
     /// The children of the node
     pub children: &'a mut [(Action, Data, Option<Self>)],
 }
@@ -93,6 +106,7 @@ where
         let root = Node {
             visits: 0,
             score: 0.0,
+            player: strategy.player_to_move(),
             children: &mut [],
         };
 
@@ -129,6 +143,7 @@ where
         let mut root = Node {
             visits: 0,
             score: 0.0,
+            player: self.strategy.player_to_move(),
             children: &mut [],
         };
 
@@ -155,12 +170,30 @@ where
     }
 
     pub fn add_node(&mut self) {
+        self.add_node_from(None);
+    }
+
+    /// Adds a node, forcing the selection of the root child with the given index
+    pub fn add_node_at(&mut self, index: usize) {
+        self.add_node_from(Some(index));
+    }
+
+    fn add_node_from(&mut self, mut root_index: Option<usize>) {
         self.strategy.reset();
         let mut tree_walker = TreeWalker::new(&mut self.root);
 
         // Selection
         let result = loop {
-            match self.strategy.select(tree_walker.leaf()) {
+            let selection = match root_index.take() {
+                Some(index) => {
+                    self.strategy
+                        .make_move(&tree_walker.leaf().children[index].0);
+                    Selection::Selection(index as u32)
+                }
+                None => self.strategy.select(tree_walker.leaf()),
+            };
+
+            match selection {
                 result @ Selection::Terminal(_) => break result,
                 result @ Selection::Selection(index) => {
                     if tree_walker.select(index as usize).is_none() {
@@ -189,6 +222,7 @@ where
                 tree_walker.leaf_mut().children[index as usize].2 = Some(Node {
                     visits: 0,
                     score: 0.0,
+                    player: self.strategy.player_to_move(),
                     children,
                 });
 
@@ -207,6 +241,10 @@ where
         &self.strategy
     }
 
+    pub fn strategy_mut(&mut self) -> &mut S {
+        &mut self.strategy
+    }
+
     pub fn root(&self) -> &Node<'a, S::Action, S::Data> {
         &self.root
     }
@@ -259,4 +297,17 @@ where
         // since it is borrowed by Self
         unsafe { self.ptrs.pop().map(|x| &mut *x) }
     }
+
+    /// Pops the next node, along with the player that made the move leading to it. For the root,
+    /// this is the player to move at the root.
+    pub fn pop_with_mover(&mut self) -> Option<(&mut Node<'a, Action, Data>, u8)> {
+        let node = self.ptrs.pop()?;
+
+        // SAFETY: There are no other pointers to anything reachable from root
+        // since it is borrowed by Self
+        unsafe {
+            let mover = (**self.ptrs.last().unwrap_or(&node)).player;
+            Some((&mut *node, mover))
+        }
+    }
 }
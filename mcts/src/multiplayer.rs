@@ -0,0 +1,56 @@
+use crate::TreeWalker;
+
+pub const MAX_PLAYERS: usize = 5;
+
+/// The reward for every player, indexed by the values returned from
+/// [`Strategy::player_to_move`](crate::Strategy::player_to_move)
+pub type Rewards = [f64; MAX_PLAYERS];
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Backup {
+    /// Every player maximises their own reward
+    MaxN,
+    /// Every other player is assumed to minimise the reward of the given player
+    Paranoid(u8),
+}
+
+impl Backup {
+    /// The score of a node from the perspective of the player that moved into it
+    pub fn score(self, rewards: &Rewards, mover: u8) -> f64 {
+        match self {
+            Backup::MaxN => rewards[mover as usize],
+            Backup::Paranoid(player) => {
+                if mover == player {
+                    rewards[player as usize]
+                } else {
+                    1.0 - rewards[player as usize]
+                }
+            }
+        }
+    }
+}
+
+/// Rewards for a game where a single player is scored, and everyone else shares the remainder
+pub fn rewards_from_score(player: u8, score: f64, num_players: usize) -> Rewards {
+    let mut rewards = [0.0; MAX_PLAYERS];
+    let others = (1.0 - score) / num_players.saturating_sub(1).max(1) as f64;
+    for (i, reward) in rewards.iter_mut().enumerate().take(num_players) {
+        *reward = if i == player as usize { score } else { others };
+    }
+
+    rewards
+}
+
+/// A replacement for [`Strategy::backpropagate`](crate::Strategy::backpropagate) for games with
+/// more than two players. Each node is scored from the perspective of the player that moved into
+/// it, as determined by the backup rule.
+pub fn backpropagate<Action, Data>(
+    rewards: &Rewards,
+    backup: Backup,
+    mut tree: TreeWalker<'_, '_, Action, Data>,
+) {
+    while let Some((node, mover)) = tree.pop_with_mover() {
+        node.visits += 1;
+        node.score += backup.score(rewards, mover);
+    }
+}
@@ -1,4 +1,4 @@
-#![no_main]
+#![cfg_attr(not(test), no_main)]
 use rand_xoshiro::rand_core::SeedableRng;
 
 #[no_mangle]
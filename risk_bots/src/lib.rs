@@ -1,4 +1,6 @@
 pub mod complex;
+pub mod random;
+pub mod registry;
 pub mod simple;
 pub mod strategy;
 pub mod very_bad;
@@ -0,0 +1,200 @@
+use attack_game::cards::is_card_set;
+use enum_map::EnumMap;
+use rand::prelude::SliceRandom;
+use risk_helper::{state::ClientState, util, ManagedPlayer};
+use risk_shared::{
+    map::{TerritoryId, EDGES},
+    query::Query,
+    record::{
+        Cause, Move, MoveAttack, MoveDefend, MoveDistributeTroops, MoveFortify, MoveRedeemCards,
+        MoveTroopsAfterAttack, PublicRecord,
+    },
+    Card,
+};
+
+/// Chooses uniformly between the legal moves of each query, so that games reach positions that
+/// the other bots never play into. Passing is one more choice among the attacks and fortifies.
+pub struct RandomBot<R: rand::Rng> {
+    rng: R,
+}
+
+impl<R: rand::Rng> RandomBot<R> {
+    pub fn new(rng: R) -> Self {
+        Self { rng }
+    }
+
+    /// Chooses one of the `count` options, or `None` for the extra option of passing
+    fn choose_or_pass(&mut self, count: usize) -> Option<usize> {
+        let choice = self.rng.gen_range(0..=count);
+        (choice < count).then_some(choice)
+    }
+
+    fn choose_card_set(&mut self, cards: &[Card]) -> Option<[Card; 3]> {
+        let mut sets = Vec::new();
+        for i in 0..cards.len() {
+            for j in i + 1..cards.len() {
+                for k in j + 1..cards.len() {
+                    let set = [cards[i], cards[j], cards[k]];
+                    if is_card_set(set) {
+                        sets.push(set);
+                    }
+                }
+            }
+        }
+
+        sets.choose(&mut self.rng).copied()
+    }
+}
+
+impl<R: rand::Rng> ManagedPlayer for RandomBot<R> {
+    fn reset(&mut self) {}
+
+    fn pre_query(&mut self, _: &ClientState, _: &Query) {}
+
+    fn query_attack(&mut self, state: &ClientState) -> Option<MoveAttack> {
+        let me = state.me().id;
+        let attacks = state
+            .territories_owned_by(Some(me))
+            .into_iter()
+            .filter(|&x| state.territories()[x].troops > 1)
+            .flat_map(|source| {
+                EDGES[source]
+                    .iter()
+                    .filter(|&&x| state.territories()[x].occupier != Some(me))
+                    .map(move |&target| (source, target))
+            })
+            .collect::<Vec<_>>();
+
+        let (attacking_territory, defending_territory) =
+            attacks[self.choose_or_pass(attacks.len())?];
+        let max_troops = std::cmp::min(3, state.territories()[attacking_territory].troops - 1);
+        Some(MoveAttack {
+            attacking_territory,
+            defending_territory,
+            attacking_troops: self.rng.gen_range(1..=max_troops),
+        })
+    }
+
+    fn query_claim_territory(&mut self, state: &ClientState) -> TerritoryId {
+        let unclaimed_territories = state.territories_owned_by(None);
+        *unclaimed_territories.choose(&mut self.rng).unwrap()
+    }
+
+    fn query_defend(&mut self, state: &ClientState, move_attack_id: usize) -> MoveDefend {
+        let PublicRecord::Move(_, Move::Attack(move_attack)) = &state.recording()[move_attack_id]
+        else {
+            unreachable!();
+        };
+
+        let max_troops = std::cmp::min(
+            state.territories()[move_attack.defending_territory].troops,
+            2,
+        );
+        MoveDefend {
+            move_attack_id,
+            defending_troops: self.rng.gen_range(1..=max_troops),
+        }
+    }
+
+    fn query_distribute_troops(
+        &mut self,
+        state: &ClientState,
+        cause: Cause,
+    ) -> MoveDistributeTroops {
+        let mut distributions = EnumMap::from_fn(|_| 0);
+        let mut total_troops = state.me().troops_remaining;
+
+        if let Some(&territory) = state.me().must_place_territory_bonus.choose(&mut self.rng) {
+            assert!(total_troops >= 2);
+            distributions[territory] += 2;
+            total_troops -= 2;
+        }
+
+        let my_territories = state.territories_owned_by(Some(state.me().id));
+        for _ in 0..total_troops {
+            distributions[*my_territories.choose(&mut self.rng).unwrap()] += 1;
+        }
+
+        MoveDistributeTroops {
+            distributions: Box::new(distributions),
+            cause,
+        }
+    }
+
+    fn query_fortify(&mut self, state: &ClientState) -> Option<MoveFortify> {
+        let my_territories = state.territories_owned_by(Some(state.me().id));
+        let sources = my_territories
+            .iter()
+            .copied()
+            .filter(|&x| state.territories()[x].troops > 1)
+            .collect::<Vec<_>>();
+
+        let source_territory = sources[self.choose_or_pass(sources.len())?];
+        let target_territory = *util::connected_territories(&my_territories, source_territory)
+            .choose(&mut self.rng)?;
+        let troop_count = self
+            .rng
+            .gen_range(1..state.territories()[source_territory].troops);
+
+        Some(MoveFortify {
+            source_territory,
+            target_territory,
+            troop_count,
+        })
+    }
+
+    fn query_place_initial_troop(&mut self, state: &ClientState) -> TerritoryId {
+        let my_territories = state.territories_owned_by(Some(state.me().id));
+        *my_territories.choose(&mut self.rng).unwrap()
+    }
+
+    /// Redeems sets until fewer than 5 cards are held, and at the start of a turn keeps redeeming
+    /// a random number of the sets that remain
+    fn query_redeem_cards(&mut self, state: &ClientState, cause: Cause) -> MoveRedeemCards {
+        let mut cards_remaining = state.me().cards.clone();
+        let mut card_sets = Vec::new();
+        loop {
+            let forced = cards_remaining.len() >= 5;
+            if !forced && (matches!(cause, Cause::PlayerEliminated) || self.rng.gen_bool(0.5)) {
+                break;
+            }
+
+            let Some(card_set) = self.choose_card_set(&cards_remaining) else {
+                assert!(!forced, "Any 5 cards contain a set");
+                break;
+            };
+
+            card_sets.push(card_set);
+            cards_remaining.retain(|x| !card_set.contains(x));
+        }
+
+        MoveRedeemCards {
+            sets: card_sets,
+            cause,
+        }
+    }
+
+    /// Moves at least as many troops as attacked with, unless fewer are left behind
+    fn query_troops_after_attack(
+        &mut self,
+        state: &ClientState,
+        record_attack_id: usize,
+    ) -> MoveTroopsAfterAttack {
+        let PublicRecord::Attack(record_attack) = state.recording()[record_attack_id] else {
+            unreachable!();
+        };
+
+        let PublicRecord::Move(_, Move::Attack(move_attack)) =
+            state.recording()[record_attack.move_attack_id]
+        else {
+            unreachable!();
+        };
+
+        let available = state.territories()[move_attack.attacking_territory].troops - 1;
+        let min_troops = std::cmp::min(move_attack.attacking_troops, available);
+        MoveTroopsAfterAttack {
+            troop_count: self.rng.gen_range(min_troops..=available),
+            record_attack_id,
+        }
+    }
+}
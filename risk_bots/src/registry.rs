@@ -0,0 +1,142 @@
+//! Creates bots by name, so that tests and tuning runs can be configured without recompiling
+
+use std::collections::BTreeMap;
+
+use attack_game::strategy::{puct, state::State};
+use rand::{seq::SliceRandom, RngCore, SeedableRng};
+use rand_xoshiro::Xoshiro256StarStar;
+use risk_helper::ManagedPlayerBot;
+use risk_shared::player::PlayerBot;
+use tunable::{Param, Tunable};
+
+use crate::{
+    complex::ComplexExample,
+    random::RandomBot,
+    simple::SimpleExample,
+    strategy::{Params, PuctBot},
+    very_bad::VeryBad,
+    very_bad13::VeryBad13,
+};
+
+pub const BOTS: [&str; 6] = [
+    "puct",
+    "complex",
+    "simple",
+    "very_bad",
+    "very_bad13",
+    "random",
+];
+
+pub type PuctParams = Params<State<'static, puct::AttackPUCT<Xoshiro256StarStar>>>;
+
+/// A bot from the registry, with any parameters that differ from its defaults
+#[derive(Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BotConfig {
+    pub bot: String,
+    #[serde(default)]
+    pub params: BTreeMap<String, f64>,
+}
+
+impl BotConfig {
+    pub fn new(bot: &str) -> Self {
+        Self {
+            bot: bot.to_owned(),
+            params: BTreeMap::new(),
+        }
+    }
+
+    /// Checks that the bot exists and that it accepts every parameter
+    pub fn validate(&self) -> Result<(), String> {
+        self.try_create().map(|_| ())
+    }
+
+    pub fn try_create(&self) -> Result<Box<dyn PlayerBot>, String> {
+        create_bot(&self.bot, self.params.iter().map(|(k, v)| (k.as_str(), *v)))
+    }
+
+    /// Panics if the config is invalid, which should be checked with `validate` beforehand
+    pub fn create(&self) -> Box<dyn PlayerBot> {
+        self.try_create().unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// The name of the bot followed by the value of every parameter, including the defaults, which
+    /// identifies it in saved results. Panics if the config is invalid.
+    pub fn id(&self) -> String {
+        let mut id = self.bot.clone();
+        for param in self.resolved_params() {
+            id.push_str(&format!(" {}={}", param.name, param.value));
+        }
+
+        id
+    }
+
+    /// Every parameter of the bot, with the overridden values
+    fn resolved_params(&self) -> Vec<Param> {
+        let params = self.params.iter().map(|(k, v)| (k.as_str(), *v));
+        match self.bot.as_str() {
+            "puct" => PuctParams::from_values(params)
+                .unwrap_or_else(|e| panic!("{e}"))
+                .all_params(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// The opponents in the seats that are not being tested
+pub fn default_opponents() -> Vec<BotConfig> {
+    vec![
+        BotConfig::new("very_bad"),
+        BotConfig::new("very_bad13"),
+        BotConfig::new("complex"),
+    ]
+}
+
+/// Chooses the bots for the three other seats. A pool of exactly three bots is seated in order,
+/// otherwise each seat is drawn from the pool at random.
+pub fn choose_opponents(pool: &[BotConfig]) -> [&BotConfig; 3] {
+    assert!(!pool.is_empty(), "The opponent pool is empty");
+    if let [a, b, c] = pool {
+        return [a, b, c];
+    }
+
+    let mut rng = rand::thread_rng();
+    std::array::from_fn(|_| pool.choose(&mut rng).unwrap())
+}
+
+pub fn create_bot<'a>(
+    name: &str,
+    params: impl IntoIterator<Item = (&'a str, f64)>,
+) -> Result<Box<dyn PlayerBot>, String> {
+    let rng = Xoshiro256StarStar::seed_from_u64(rand::thread_rng().next_u64());
+    let mut params = params.into_iter().peekable();
+    if name != "puct" {
+        if let Some((key, _)) = params.peek() {
+            return Err(format!("{name} has no parameter {key}"));
+        }
+    }
+
+    let bot: Box<dyn PlayerBot> = match name {
+        "puct" => {
+            let params = PuctParams::from_values(params)?;
+            Box::new(ManagedPlayerBot::new(PuctBot::with_params(params, rng)))
+        }
+        "complex" => Box::new(ManagedPlayerBot::new(ComplexExample::new(rng))),
+        "simple" => Box::new(ManagedPlayerBot::new(SimpleExample::new(rng))),
+        "very_bad" => Box::new(ManagedPlayerBot::new(VeryBad::new())),
+        "very_bad13" => Box::new(ManagedPlayerBot::new(VeryBad13::new())),
+        "random" => Box::new(ManagedPlayerBot::new(RandomBot::new(rng))),
+        _ => return Err(format!("Unknown bot {name}, expected one of {BOTS:?}")),
+    };
+
+    Ok(bot)
+}
+
+/// The parameters of the bot, which can be set in its config
+pub fn params(name: &str) -> Result<Vec<Param>, String> {
+    match name {
+        "puct" => Ok(PuctParams::default().all_params()),
+        _ if BOTS.contains(&name) => Ok(Vec::new()),
+        _ => Err(format!("Unknown bot {name}, expected one of {BOTS:?}")),
+    }
+}
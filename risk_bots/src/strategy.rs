@@ -1,13 +1,18 @@
 use std::collections::VecDeque;
 
 use attack_game::{
-    game::PlayerMove,
+    cards,
+    draft::{self, DraftGame},
+    game::{AttackGame, PlayerMove},
+    placement,
     strategy::{
         puct,
         state::{State, StatefulStrategy},
     },
 };
 use enum_map::EnumMap;
+use rand::{RngCore, SeedableRng};
+use rand_xoshiro::Xoshiro256StarStar;
 use risk_helper::{state::ClientState, util, ManagedPlayer};
 use risk_shared::{
     map::{TerritoryId, EDGES},
@@ -18,41 +23,90 @@ use risk_shared::{
         MoveTroopsAfterAttack, PublicRecord,
     },
 };
+use tunable::Tunable;
 
 pub type PuctBot<'a, R> = StatefulStrategyBot<State<'a, puct::AttackPUCT<R>>>;
 
+/// The number of initial troops placed from each plan before searching again
+const INITIAL_PLACEMENT_PLAN_LENGTH: usize = 5;
+
 pub struct Params<S: StatefulStrategy> {
     pub first_friendly_troop_reduction: f64,
     pub first_enemy_troop_reduction: f64,
+    pub card_params: cards::Params,
+    pub draft_params: draft::Params,
+    pub search_initial_placement: bool,
     pub strategy_params: S::Params,
 }
 
+impl<S: StatefulStrategy> Default for Params<S>
+where
+    S::Params: Default,
+{
+    fn default() -> Self {
+        Self {
+            first_friendly_troop_reduction: 0.9711985622851357,
+            first_enemy_troop_reduction: 0.47578774202200713,
+            card_params: cards::Params::default(),
+            draft_params: draft::Params::default(),
+            search_initial_placement: false,
+            strategy_params: S::Params::default(),
+        }
+    }
+}
+
+tunable::tunable! {
+    impl[S: StatefulStrategy] Tunable for Params<S> where [S::Params: Tunable] {
+        first_friendly_troop_reduction: float(0.0, 1.0, 0.05, 0.01),
+        first_enemy_troop_reduction: float(0.0, 1.0, 0.05, 0.01),
+        card_params: nested(""),
+        draft_params: nested("draft_"),
+        search_initial_placement: fixed,
+        strategy_params: nested(""),
+    }
+}
+
 pub struct StatefulStrategyBot<S: StatefulStrategy> {
     mcts: S,
     needs_search_reset: bool,
-    repeat_move: Option<PlayerMove>,
+    repeat_move: Option<(PlayerMove, u8)>,
     first_friendly_troop_reduction: f64,
     first_enemy_troop_reduction: f64,
+    card_params: cards::Params,
+    draft_params: draft::Params,
+    search_initial_placement: bool,
+    initial_placement_plan: Vec<TerritoryId>,
+    rng: Xoshiro256StarStar,
 }
 
 impl<S: StatefulStrategy> StatefulStrategyBot<S> {
-    pub fn new(rng: S::Rng) -> Self {
+    pub fn new(mut rng: S::Rng) -> Self {
         Self {
+            rng: Xoshiro256StarStar::seed_from_u64(rng.next_u64()),
             mcts: S::from_rng(rng),
             needs_search_reset: true,
             repeat_move: None,
             first_enemy_troop_reduction: 0.47578774202200713,
             first_friendly_troop_reduction: 0.9711985622851357,
+            card_params: cards::Params::default(),
+            draft_params: draft::Params::default(),
+            search_initial_placement: false,
+            initial_placement_plan: Vec::new(),
         }
     }
 
-    pub fn with_params(params: Params<S>, rng: S::Rng) -> Self {
+    pub fn with_params(params: Params<S>, mut rng: S::Rng) -> Self {
         Self {
+            rng: Xoshiro256StarStar::seed_from_u64(rng.next_u64()),
             mcts: S::with_params(params.strategy_params, rng),
             needs_search_reset: true,
             repeat_move: None,
             first_friendly_troop_reduction: params.first_friendly_troop_reduction,
             first_enemy_troop_reduction: params.first_enemy_troop_reduction,
+            card_params: params.card_params,
+            draft_params: params.draft_params,
+            search_initial_placement: params.search_initial_placement,
+            initial_placement_plan: Vec::new(),
         }
     }
 }
@@ -67,7 +121,7 @@ impl<S: StatefulStrategy> ManagedPlayer for StatefulStrategyBot<S> {
     fn pre_query(&mut self, state: &ClientState, query: &risk_shared::query::Query) {
         if !matches!(
             query.details,
-            QueryDetails::Attack | QueryDetails::TroopsAfterAttack(_)
+            QueryDetails::Attack | QueryDetails::TroopsAfterAttack(_) | QueryDetails::Fortify
         ) {
             self.needs_search_reset = true;
         }
@@ -87,8 +141,11 @@ impl<S: StatefulStrategy> ManagedPlayer for StatefulStrategyBot<S> {
                             dest: attack.defending_territory,
                         };
 
-                        self.mcts
-                            .make_moves(player, (r.attacking_lost as u8, r.defending_lost as u8));
+                        self.mcts.make_moves(
+                            player,
+                            attack.attacking_troops as u8,
+                            (r.attacking_lost as u8, r.defending_lost as u8),
+                        );
                     }
                 }
             }
@@ -97,16 +154,10 @@ impl<S: StatefulStrategy> ManagedPlayer for StatefulStrategyBot<S> {
 
     fn query_attack(&mut self, state: &ClientState) -> Option<MoveAttack> {
         if self.needs_search_reset {
-            self.needs_search_reset = false;
-            let troop_counts = EnumMap::from_fn(|t| state.territories()[t].troops);
-            let occupiers = encode_occupiers(state);
-            self.mcts
-                .reset(troop_counts, occupiers, state.card_sets_redeemed());
-
-            self.repeat_move = None;
+            self.reset_search(state);
         }
 
-        if let Some(repeat_move) = self.repeat_move {
+        if let Some((repeat_move, dice)) = self.repeat_move {
             let PlayerMove { origin, dest } = repeat_move;
             if state.territories()[origin].troops > 1
                 && state.territories()[dest].occupier != Some(state.me().id)
@@ -114,106 +165,39 @@ impl<S: StatefulStrategy> ManagedPlayer for StatefulStrategyBot<S> {
                 return Some(MoveAttack {
                     attacking_territory: origin,
                     defending_territory: dest,
-                    attacking_troops: std::cmp::min(3, state.territories()[origin].troops - 1),
+                    attacking_troops: std::cmp::min(
+                        u32::from(dice),
+                        state.territories()[origin].troops - 1,
+                    ),
                 });
             } else {
                 self.repeat_move = None;
             }
         }
 
-        let (mov, repeat) = self.mcts.get_move()?;
+        let (mov, dice, repeat) = self.mcts.get_move()?;
         if repeat {
-            self.repeat_move = Some(mov);
+            self.repeat_move = Some((mov, dice));
         }
 
         Some(MoveAttack {
             attacking_territory: mov.origin,
             defending_territory: mov.dest,
-            attacking_troops: std::cmp::min(3, state.territories()[mov.origin].troops - 1),
+            attacking_troops: u32::from(dice),
         })
     }
 
     fn query_claim_territory(&mut self, state: &ClientState) -> TerritoryId {
-        let mut unclaimed_territories = state.territories_owned_by(None);
-        let my_territories = state.territories_owned_by(Some(state.me().id));
-
-        let adjacent_territories = util::adjacent_territories(&my_territories);
-
-        // Claim the one with the most of our territories adjacent
-        if let Some(selected) = unclaimed_territories
-            .iter()
-            .filter(|x| adjacent_territories.contains(x))
-            .copied()
-            .max_by(|&x, &y| {
-                let x_adj = EDGES[x]
-                    .iter()
-                    .filter(|x| my_territories.contains(x))
-                    .count();
-
-                let y_adj = EDGES[y]
-                    .iter()
-                    .filter(|x| my_territories.contains(x))
-                    .count();
-
-                // Prefer the one with the highest degree
-                x_adj.cmp(&y_adj).then(EDGES[x].len().cmp(&EDGES[y].len()))
-            })
-        {
-            return selected;
-        }
-
-        // The default order is very bad, so we reverse first
-        unclaimed_territories.reverse();
-        let distances = if my_territories.is_empty() {
-            EnumMap::from_fn(|_| u32::MAX)
-        } else {
-            get_territory_distances(&my_territories)
-        };
-
-        // Attempt to claim the territories that are closest to our territories
-        unclaimed_territories
-            .into_iter()
-            .min_by(|&x, &y| {
-                distances[x]
-                    .cmp(&distances[y])
-                    .then_with(|| {
-                        // Prefer those with the most adjacent unclaimed territories
-                        let x_count = EDGES[x]
-                            .iter()
-                            .filter(|&&x| state.territories()[x].occupier.is_none())
-                            .count();
-
-                        let y_count = EDGES[y]
-                            .iter()
-                            .filter(|&&x| state.territories()[x].occupier.is_none())
-                            .count();
-
-                        x_count.cmp(&y_count).reverse()
-                    })
-                    .then_with(|| {
-                        // Prefer those with the fewest adjacent enemy territories
-                        let x_count = EDGES[x]
-                            .iter()
-                            .filter(|&&x| {
-                                state.territories()[x]
-                                    .occupier
-                                    .map_or(false, |x| x != state.me().id)
-                            })
-                            .count();
-
-                        let y_count = EDGES[y]
-                            .iter()
-                            .filter(|&&x| {
-                                state.territories()[x]
-                                    .occupier
-                                    .map_or(false, |x| x != state.me().id)
-                            })
-                            .count();
-
-                        x_count.cmp(&y_count)
-                    })
-            })
-            .unwrap()
+        let player_id_map = encode_players(state);
+        let claims = EnumMap::from_fn(|t| {
+            state.territories()[t]
+                .occupier
+                .map(|player| player_id_map[player])
+        });
+
+        let num_players = state.players().values().filter(|x| x.alive).count() as u8;
+        let game = DraftGame::new(claims, PlayerId::P0, num_players);
+        draft::search(game, self.draft_params, &mut self.rng).unwrap()
     }
 
     fn query_defend(&mut self, state: &ClientState, move_attack_id: usize) -> MoveDefend {
@@ -254,6 +238,7 @@ impl<S: StatefulStrategy> ManagedPlayer for StatefulStrategyBot<S> {
                 occupiers,
                 &mut distributions,
                 state.card_sets_redeemed(),
+                encode_card_counts(state),
             );
 
             self.needs_search_reset = false;
@@ -266,6 +251,18 @@ impl<S: StatefulStrategy> ManagedPlayer for StatefulStrategyBot<S> {
     }
 
     fn query_fortify(&mut self, state: &ClientState) -> Option<MoveFortify> {
+        if self.needs_search_reset {
+            self.reset_search(state);
+        }
+
+        if let Some(fortify) = self.mcts.get_fortify() {
+            return fortify.map(|(mov, troop_count)| MoveFortify {
+                source_territory: mov.origin,
+                target_territory: mov.dest,
+                troop_count,
+            });
+        }
+
         // Fortify the non-border territory with most troops to the closest border
         let my_territories = state.territories_owned_by(Some(state.me().id));
         let border_territories = util::border_territories(&my_territories);
@@ -302,6 +299,14 @@ impl<S: StatefulStrategy> ManagedPlayer for StatefulStrategyBot<S> {
     }
 
     fn query_place_initial_troop(&mut self, state: &ClientState) -> TerritoryId {
+        if self.search_initial_placement {
+            if self.initial_placement_plan.is_empty() {
+                self.plan_initial_placement(state);
+            }
+
+            return self.initial_placement_plan.pop().unwrap();
+        }
+
         let my_territories = state.territories_owned_by(Some(state.me().id));
         let border_territories = util::border_territories(&my_territories);
 
@@ -316,33 +321,29 @@ impl<S: StatefulStrategy> ManagedPlayer for StatefulStrategyBot<S> {
     }
 
     fn query_redeem_cards(&mut self, state: &ClientState, cause: Cause) -> MoveRedeemCards {
-        let mut card_sets = Vec::new();
-        let mut cards_remaining = state.me().cards.clone();
-
-        while cards_remaining.len() >= 5 {
-            let card_set = util::get_card_set(&cards_remaining).unwrap();
-            card_sets.push(card_set);
-            cards_remaining.retain(|x| !card_set.contains(x));
-        }
-
-        if cause.is_turn_started() {
-            let territories_held = TerritoryId::ALL
-                .into_iter()
-                .filter(|&x| state.territories()[x].occupier == Some(state.me().id))
-                .count();
-
-            if state.card_sets_redeemed() > 6 || territories_held <= 5 {
-                while let Some(card_set) = util::get_card_set(&cards_remaining) {
-                    card_sets.push(card_set);
-                    cards_remaining.retain(|x| !card_set.contains(x));
-                }
-            }
-        }
-
-        MoveRedeemCards {
-            sets: card_sets,
-            cause,
-        }
+        let mut game = AttackGame::new();
+        game.set_state(
+            EnumMap::from_fn(|t| state.territories()[t].troops),
+            encode_occupiers(state),
+            state.card_sets_redeemed(),
+        );
+
+        let opponent_card_counts = state
+            .players()
+            .values()
+            .filter(|x| x.alive && x.id != state.me().id)
+            .map(|x| x.card_count)
+            .collect::<Vec<_>>();
+
+        let sets = cards::choose_redemption(
+            &game,
+            &state.me().cards,
+            &opponent_card_counts,
+            cause.is_turn_started(),
+            &self.card_params,
+        );
+
+        MoveRedeemCards { sets, cause }
     }
 
     fn query_troops_after_attack(
@@ -360,14 +361,92 @@ impl<S: StatefulStrategy> ManagedPlayer for StatefulStrategyBot<S> {
             unreachable!();
         };
 
+        let troop_count = if self.needs_search_reset {
+            None
+        } else {
+            self.mcts.get_troops_after_attack()
+        };
+
         MoveTroopsAfterAttack {
             record_attack_id,
-            troop_count: state.territories()[move_attack.attacking_territory].troops - 1,
+            troop_count: troop_count
+                .unwrap_or(state.territories()[move_attack.attacking_territory].troops - 1),
         }
     }
 }
 
 impl<S: StatefulStrategy> StatefulStrategyBot<S> {
+    fn reset_search(&mut self, state: &ClientState) {
+        self.needs_search_reset = false;
+        let troop_counts = EnumMap::from_fn(|t| state.territories()[t].troops);
+        let occupiers = encode_occupiers(state);
+        self.mcts.reset(
+            troop_counts,
+            occupiers,
+            state.card_sets_redeemed(),
+            encode_card_counts(state),
+        );
+
+        self.repeat_move = None;
+    }
+
+    fn plan_initial_placement(&mut self, state: &ClientState) {
+        let player_id_map = encode_players(state);
+        let occupiers = encode_occupiers(state);
+        let mut game = AttackGame::new();
+        game.set_state(
+            EnumMap::from_fn(|t| state.territories()[t].troops),
+            occupiers,
+            state.card_sets_redeemed(),
+        );
+
+        let mut troops_remaining = EnumMap::from_fn(|_| 0);
+        for player in state.players().values().filter(|x| x.alive) {
+            troops_remaining[player_id_map[player.id]] = player.troops_remaining;
+        }
+
+        let players_before_us = state
+            .turn_order()
+            .iter()
+            .take_while(|&&x| x != state.me().id)
+            .filter(|&&x| state.players()[x].alive)
+            .count();
+
+        placement::project_initial_troops(&mut game, troops_remaining, players_before_us);
+
+        let mut distributions = EnumMap::from_fn(|_| 0);
+        self.mcts.place_troops(
+            state.me().troops_remaining,
+            EnumMap::from_fn(|t| game.troops(t)),
+            occupiers,
+            &mut distributions,
+            state.card_sets_redeemed(),
+            encode_card_counts(state),
+        );
+
+        self.needs_search_reset = true;
+
+        // The plan is popped from the back, so the largest stacks are placed last, giving the
+        // other players less time to respond to them
+        let mut territories = TerritoryId::ALL
+            .into_iter()
+            .filter(|&t| distributions[t] > 0)
+            .collect::<Vec<_>>();
+        territories.sort_by_key(|&t| std::cmp::Reverse(distributions[t]));
+
+        self.initial_placement_plan = territories
+            .into_iter()
+            .flat_map(|t| std::iter::repeat_n(t, distributions[t] as usize))
+            .collect();
+
+        // Only part of the plan is used before searching again with the other players' placements
+        let unused = self
+            .initial_placement_plan
+            .len()
+            .saturating_sub(INITIAL_PLACEMENT_PLAN_LENGTH);
+        self.initial_placement_plan.drain(..unused);
+    }
+
     fn territory_strength(&self, state: &ClientState, territory: TerritoryId) -> f64 {
         let adjacent_territories = util::adjacent_territories(&[territory]);
         let enemy_strength = adjacent_territories
@@ -391,29 +470,6 @@ impl<S: StatefulStrategy> StatefulStrategyBot<S> {
     }
 }
 
-fn get_territory_distances(owned: &[TerritoryId]) -> EnumMap<TerritoryId, u32> {
-    assert_ne!(owned.len(), 0);
-    let mut distances = EnumMap::from_fn(|_| u32::MAX);
-    let mut queue = VecDeque::new();
-
-    for &territory in owned {
-        distances[territory] = 0;
-        queue.push_front(territory);
-    }
-
-    while let Some(territory) = queue.pop_back() {
-        for &adjacent in EDGES[territory] {
-            if distances[adjacent] == u32::MAX {
-                distances[adjacent] = distances[territory] + 1;
-                queue.push_front(adjacent);
-            }
-        }
-    }
-
-    assert!(distances.values().all(|x| *x != u32::MAX));
-    distances
-}
-
 fn find_next_step_to_set(
     source: TerritoryId,
     forbidden_set: EnumMap<TerritoryId, bool>,
@@ -455,7 +511,8 @@ fn find_next_step_to_set(
     }
 }
 
-fn encode_occupiers(state: &ClientState) -> EnumMap<TerritoryId, PlayerId> {
+/// Maps the alive players to player ids based on turn order, starting from us
+pub fn encode_players(state: &ClientState) -> EnumMap<PlayerId, PlayerId> {
     let my_turn_index = state
         .turn_order()
         .iter()
@@ -471,5 +528,24 @@ fn encode_occupiers(state: &ClientState) -> EnumMap<TerritoryId, PlayerId> {
         player_id_map[player] = PlayerId::n(i as u8).unwrap();
     }
 
+    player_id_map
+}
+
+pub fn encode_occupiers(state: &ClientState) -> EnumMap<TerritoryId, PlayerId> {
+    let player_id_map = encode_players(state);
     EnumMap::from_fn(|t| player_id_map[state.territories()[t].occupier.unwrap()])
 }
+
+pub fn encode_card_counts(state: &ClientState) -> EnumMap<PlayerId, u32> {
+    let player_id_map = encode_players(state);
+    let mut card_counts = EnumMap::from_fn(|_| 0);
+    for player in state.players().values().filter(|x| x.alive) {
+        card_counts[player_id_map[player.id]] = if player.id == state.me().id {
+            state.me().cards.len() as u32
+        } else {
+            player.card_count as u32
+        };
+    }
+
+    card_counts
+}
@@ -0,0 +1,123 @@
+//! Openings that games can start from, so that different bots can be tested from the same start
+
+use std::collections::HashSet;
+
+use enum_map::EnumMap;
+use rand::seq::SliceRandom;
+use risk_shared::{map::TerritoryId, player::PlayerId, Card, CARD_COUNT};
+
+use crate::NUM_STARTING_TROOPS;
+
+/// The phase that an opening is played until
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    /// Every territory has been claimed
+    #[default]
+    Claim,
+    /// Every initial troop has been placed as well
+    Placement,
+}
+
+/// The start of a game, which is replayed instead of querying the players
+#[derive(Clone, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Opening {
+    pub turn_order: [PlayerId; 5],
+    /// The ids of the cards after the first shuffle, which are drawn from the end
+    pub deck: Vec<u8>,
+    /// Each territory claimed, followed by each initial troop placed if the opening includes them
+    pub moves: Vec<(PlayerId, TerritoryId)>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Book {
+    pub phase: Phase,
+    pub openings: Vec<Opening>,
+}
+
+impl Opening {
+    pub fn deck(&self) -> Vec<Card> {
+        self.deck.iter().map(|&x| Card::new(x).unwrap()).collect()
+    }
+
+    /// Checks that the opening is a whole phase of a game. The order of the initial troops is
+    /// checked when the opening is replayed.
+    pub fn validate(&self, phase: Phase) -> Result<(), String> {
+        if self.turn_order.iter().collect::<HashSet<_>>().len() != self.turn_order.len() {
+            return Err("The turn order repeats a player".to_owned());
+        }
+
+        let deck = self.deck.iter().collect::<HashSet<_>>();
+        if self.deck.len() != CARD_COUNT || deck.len() != CARD_COUNT {
+            return Err("The deck is not every card".to_owned());
+        }
+
+        if self.deck.iter().any(|&x| Card::new(x).is_none()) {
+            return Err("The deck has an unknown card".to_owned());
+        }
+
+        let num_territories = TerritoryId::ALL.len();
+        let num_moves = match phase {
+            Phase::Claim => num_territories,
+            Phase::Placement => NUM_STARTING_TROOPS as usize * self.turn_order.len(),
+        };
+        if self.moves.len() != num_moves {
+            return Err(format!(
+                "Expected {num_moves} moves for the {phase:?} phase, found {}",
+                self.moves.len()
+            ));
+        }
+
+        let (claims, placements) = self.moves.split_at(num_territories);
+        let mut occupiers = EnumMap::<TerritoryId, Option<PlayerId>>::default();
+        for (i, &(player, territory)) in claims.iter().enumerate() {
+            if player != self.turn_order[i % self.turn_order.len()] {
+                return Err(format!("Claim {i} is out of turn"));
+            }
+
+            if occupiers[territory].replace(player).is_some() {
+                return Err(format!("{territory:?} is claimed twice"));
+            }
+        }
+
+        for (i, &(player, territory)) in placements.iter().enumerate() {
+            if occupiers[territory] != Some(player) {
+                return Err(format!(
+                    "Initial troop {i} is placed on another player's territory"
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Book {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let contents =
+            std::fs::read_to_string(path).map_err(|e| format!("Cannot read {path}: {e}"))?;
+        let book = serde_json::from_str::<Self>(&contents)
+            .map_err(|e| format!("Cannot parse {path}: {e}"))?;
+
+        if book.openings.is_empty() {
+            return Err(format!("{path} has no openings"));
+        }
+
+        for (i, opening) in book.openings.iter().enumerate() {
+            opening
+                .validate(book.phase)
+                .map_err(|e| format!("Opening {i} of {path} is invalid: {e}"))?;
+        }
+
+        Ok(book)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let contents = serde_json::to_string(self).unwrap();
+        std::fs::write(path, contents).map_err(|e| format!("Cannot write {path}: {e}"))
+    }
+
+    pub fn choose(&self, rng: &mut impl rand::Rng) -> &Opening {
+        self.openings.choose(rng).unwrap()
+    }
+}
@@ -1,11 +1,13 @@
 use enum_map::EnumMap;
 use rand::prelude::SliceRandom;
 use risk_shared::{
+    map::TerritoryId,
     player::{Player, PlayerBot, PlayerId},
     record::{Cause, Move, Record, StartGame, TerritoryConquered},
 };
 
 use crate::{
+    book::{Opening, Phase},
     player::PlayerConnection,
     state::{record, EngineState},
     MAX_GAME_RECORDING_SIZE, NUM_STARTING_TROOPS,
@@ -16,16 +18,99 @@ pub enum GameResult {
     Success(PlayerId),
 }
 
+/// How a game between the players in the first two seats is scored
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Statistic {
+    /// A win for the player that wins the game, and a draw if neither does
+    #[default]
+    Winner,
+    /// A win for the player that is eliminated last, and a draw if neither is eliminated. This
+    /// still scores the games won by the other players.
+    Finish,
+}
+
+type Inspector = Box<dyn FnMut(&EngineState)>;
+
 pub struct GameEngine {
     players: EnumMap<PlayerId, PlayerConnection<Box<dyn PlayerBot>>>,
     state: EngineState,
+    opening: Option<Opening>,
+    /// The number of moves of the opening that have been replayed
+    opening_moves: usize,
+    inspector: Option<Inspector>,
 }
 
 impl GameEngine {
     pub fn new(players: EnumMap<PlayerId, PlayerConnection<Box<dyn PlayerBot>>>) -> Self {
+        Self::with_seed(players, rand::random())
+    }
+
+    /// The turn order, the deck and the dice are all decided by the seed
+    pub fn with_seed(
+        players: EnumMap<PlayerId, PlayerConnection<Box<dyn PlayerBot>>>,
+        seed: u64,
+    ) -> Self {
         GameEngine {
-            state: EngineState::new(),
+            state: EngineState::with_seed(seed),
             players,
+            opening: None,
+            opening_moves: 0,
+            inspector: None,
+        }
+    }
+
+    /// Starts from the opening instead of querying the players for its moves. The turn order and
+    /// the first deck come from the opening, and the rest of the game is decided by the seed.
+    pub fn with_opening(
+        players: EnumMap<PlayerId, PlayerConnection<Box<dyn PlayerBot>>>,
+        seed: u64,
+        opening: Opening,
+    ) -> Self {
+        GameEngine {
+            opening: Some(opening),
+            ..Self::with_seed(players, seed)
+        }
+    }
+
+    /// Calls `inspector` with the state after each record is committed, so that the engine can be
+    /// checked as the game is played
+    pub fn set_inspector(&mut self, inspector: impl FnMut(&EngineState) + 'static) {
+        self.inspector = Some(Box::new(inspector));
+    }
+
+    /// The players that have been eliminated, in the order they were eliminated
+    pub fn eliminated(&self) -> Vec<PlayerId> {
+        self.state
+            .recording()
+            .iter()
+            .filter_map(|record| match record {
+                Record::PlayerEliminated(r) => Some(r.player),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The score of the first seat against the second in half points, from 0 to 2, once the game
+    /// has ended with `result`
+    pub fn score(&self, result: &GameResult, statistic: Statistic) -> usize {
+        match statistic {
+            Statistic::Winner => match result {
+                GameResult::Success(PlayerId::P0) => 2,
+                GameResult::Success(PlayerId::P1) => 0,
+                _ => 1,
+            },
+            Statistic::Finish => {
+                // Players that are still alive are ranked after every player that was eliminated
+                let eliminated = self.eliminated();
+                let rank = |player| eliminated.iter().position(|&x| x == player);
+                match (rank(PlayerId::P0), rank(PlayerId::P1)) {
+                    (None, None) => 1,
+                    (None, Some(_)) => 2,
+                    (Some(_), None) => 0,
+                    (Some(x), Some(y)) => 2 * usize::from(x > y),
+                }
+            }
         }
     }
 
@@ -37,20 +122,82 @@ impl GameEngine {
         self.run_game()
     }
 
-    fn run_game(&mut self) -> GameResult {
-        let turn_order = {
-            let mut turn_order = PlayerId::ALL;
-            turn_order.shuffle(&mut rand::thread_rng());
-            turn_order
+    /// Plays the game until the end of the phase, and returns its moves so far so that other games
+    /// can start from the same position
+    pub fn play_opening(&mut self, phase: Phase) -> Opening {
+        for player in self.players.values_mut() {
+            player.reset();
+        }
+
+        self.start_game();
+        self.start_claim_territories_phase();
+        if phase == Phase::Placement {
+            self.start_place_initial_troops_phase();
+        }
+
+        let moves = self
+            .state
+            .recording()
+            .iter()
+            .filter_map(|record| match *record {
+                Record::Move(player, Move::ClaimTerritory(territory))
+                | Record::Move(player, Move::PlaceInitialTroop(territory)) => {
+                    Some((player, territory))
+                }
+                _ => None,
+            })
+            .collect();
+
+        Opening {
+            turn_order: self.state.turn_order(),
+            deck: self.state.deck().iter().map(|x| x.id()).collect(),
+            moves,
+        }
+    }
+
+    fn commit(&mut self, record: Record) {
+        self.state.commit(record);
+        if let Some(inspector) = &mut self.inspector {
+            inspector(&self.state);
+        }
+    }
+
+    fn start_game(&mut self) {
+        let turn_order = match &self.opening {
+            Some(opening) => opening.turn_order,
+            None => {
+                let mut turn_order = PlayerId::ALL;
+                turn_order.shuffle(self.state.rng());
+                turn_order
+            }
         };
 
-        self.state.commit(Record::StartGame(Box::new(StartGame {
+        self.commit(Record::StartGame(Box::new(StartGame {
             turn_order,
             players: EnumMap::from_fn(|x| Player::new(x, NUM_STARTING_TROOPS)),
         })));
 
-        self.state.commit(Record::ShuffledCards);
+        self.commit(Record::ShuffledCards);
+        if let Some(opening) = &self.opening {
+            self.state.set_deck(opening.deck());
+        }
+    }
+
+    /// The next move of the opening, which must be made by `player`, or `None` if the opening has
+    /// finished
+    fn next_opening_move(&mut self, player: PlayerId) -> Option<TerritoryId> {
+        let &(expected, territory) = self.opening.as_ref()?.moves.get(self.opening_moves)?;
+        assert_eq!(
+            player, expected,
+            "The opening does not follow the turn order"
+        );
+
+        self.opening_moves += 1;
+        Some(territory)
+    }
 
+    fn run_game(&mut self) -> GameResult {
+        self.start_game();
         self.start_claim_territories_phase();
         self.start_place_initial_troops_phase();
 
@@ -79,7 +226,7 @@ impl GameEngine {
         }
 
         let winner = self.state.players().values().find(|x| x.alive).unwrap().id;
-        self.state.commit(Record::Winner(winner));
+        self.commit(Record::Winner(winner));
         GameResult::Success(winner)
     }
 
@@ -92,12 +239,13 @@ impl GameEngine {
             .any(|x| x.occupier.is_none())
         {
             let player_id = self.state.turn_order()[turn];
-            let connection = &mut self.players[player_id];
             turn = (turn + 1) % 5;
 
-            let territory = connection.query_claim_territory(&self.state);
-            self.state
-                .commit(Record::Move(player_id, Move::ClaimTerritory(territory)));
+            let territory = match self.next_opening_move(player_id) {
+                Some(territory) => territory,
+                None => self.players[player_id].query_claim_territory(&self.state),
+            };
+            self.commit(Record::Move(player_id, Move::ClaimTerritory(territory)));
         }
     }
 
@@ -110,7 +258,6 @@ impl GameEngine {
             .any(|x| x.troops_remaining > 0)
         {
             let player_id = self.state.turn_order()[turn];
-            let connection = &mut self.players[player_id];
             turn = (turn + 1) % 5;
 
             let player = &self.state.players()[player_id];
@@ -118,26 +265,25 @@ impl GameEngine {
                 continue;
             }
 
-            let territory = connection.query_place_initial_troop(&self.state);
-            self.state
-                .commit(Record::Move(player_id, Move::PlaceInitialTroop(territory)));
+            let territory = match self.next_opening_move(player_id) {
+                Some(territory) => territory,
+                None => self.players[player_id].query_place_initial_troop(&self.state),
+            };
+            self.commit(Record::Move(player_id, Move::PlaceInitialTroop(territory)));
         }
     }
 
     fn troop_phase(&mut self, turn: usize) {
         let player_id = self.state.turn_order()[turn];
-        let connection = &mut self.players[player_id];
-
-        self.state
-            .commit(record::start_turn(&self.state, player_id));
+        self.commit(record::start_turn(&self.state, player_id));
 
+        let connection = &mut self.players[player_id];
         let response = connection.query_redeem_cards(&self.state, Cause::TurnStarted);
-        self.state
-            .commit(Record::Move(player_id, Move::RedeemCards(response)));
+        self.commit(Record::Move(player_id, Move::RedeemCards(response)));
 
+        let connection = &mut self.players[player_id];
         let response = connection.query_distribute_troops(&self.state, Cause::TurnStarted);
-        self.state
-            .commit(Record::Move(player_id, Move::DistributeTroops(response)));
+        self.commit(Record::Move(player_id, Move::DistributeTroops(response)));
     }
 
     fn attack_phase(&mut self, turn: usize) {
@@ -147,7 +293,7 @@ impl GameEngine {
         loop {
             let connection = &mut self.players[player_id];
             let attack = connection.query_attack(&self.state);
-            self.state.commit(Record::move_attack(player_id, attack));
+            self.commit(Record::move_attack(player_id, attack));
             let move_attack_id = self.state.recording().len() - 1;
 
             let Some(attack) = attack else {
@@ -161,36 +307,35 @@ impl GameEngine {
             };
 
             let defend = self.players[defending_player].query_defend(&self.state, move_attack_id);
-            self.state
-                .commit(Record::Move(defending_player, Move::Defend(defend)));
+            self.commit(Record::Move(defending_player, Move::Defend(defend)));
             let move_defend_id = self.state.recording().len() - 1;
 
-            let record_attack = record::attack(&self.state, move_attack_id, move_defend_id);
-            self.state.commit(Record::Attack(record_attack));
+            let record_attack = record::attack(&mut self.state, move_attack_id, move_defend_id);
+            self.commit(Record::Attack(record_attack));
             let record_attack_id = self.state.recording().len() - 1;
 
             if record_attack.territory_conquered {
                 conquered_territory = true;
                 let record = TerritoryConquered { record_attack_id };
 
-                self.state.commit(Record::TerritoryConquered(record));
+                self.commit(Record::TerritoryConquered(record));
             }
 
             if record_attack.defender_eliminated {
                 let record =
                     record::player_eliminated(&self.state, record_attack_id, defending_player);
-                self.state.commit(Record::PlayerEliminated(record));
+                self.commit(Record::PlayerEliminated(record));
 
                 if self.state.players().values().filter(|x| x.alive).count() == 1 {
                     return;
                 }
             }
 
-            let connection = &mut self.players[player_id];
             // Move troops after attack
             if record_attack.territory_conquered {
+                let connection = &mut self.players[player_id];
                 let response = connection.query_troops_after_attack(&self.state, record_attack_id);
-                self.state.commit(Record::Move(
+                self.commit(Record::Move(
                     player_id,
                     Move::MoveTroopsAfterAttack(response),
                 ));
@@ -198,24 +343,24 @@ impl GameEngine {
 
             if record_attack.defender_eliminated && self.state.players()[player_id].cards.len() > 6
             {
+                let connection = &mut self.players[player_id];
                 let response = connection.query_redeem_cards(&self.state, Cause::PlayerEliminated);
-                self.state
-                    .commit(Record::Move(player_id, Move::RedeemCards(response)));
+                self.commit(Record::Move(player_id, Move::RedeemCards(response)));
 
+                let connection = &mut self.players[player_id];
                 let response =
                     connection.query_distribute_troops(&self.state, Cause::PlayerEliminated);
-                self.state
-                    .commit(Record::Move(player_id, Move::DistributeTroops(response)));
+                self.commit(Record::Move(player_id, Move::DistributeTroops(response)));
             }
         }
 
         if conquered_territory {
             if self.state.deck().is_empty() {
-                self.state.commit(Record::ShuffledCards);
+                self.commit(Record::ShuffledCards);
             }
 
             let record = record::drew_card(&mut self.state, player_id);
-            self.state.commit(Record::DrewCard(record));
+            self.commit(Record::DrewCard(record));
         }
     }
 
@@ -223,6 +368,6 @@ impl GameEngine {
         let player_id = self.state.turn_order()[turn];
         let connection = &mut self.players[player_id];
         let response = connection.query_fortify(&self.state);
-        self.state.commit(Record::move_fortify(player_id, response));
+        self.commit(Record::move_fortify(player_id, response));
     }
 }
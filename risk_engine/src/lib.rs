@@ -1,3 +1,4 @@
+pub mod book;
 pub mod censor;
 pub mod game_engine;
 pub mod player;
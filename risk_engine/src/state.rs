@@ -2,6 +2,7 @@ pub mod mutate;
 pub mod record;
 
 use enum_map::EnumMap;
+use rand::{rngs::StdRng, SeedableRng};
 use risk_shared::record::Record;
 use risk_shared::{
     map::TerritoryId,
@@ -19,6 +20,10 @@ pub struct EngineState {
     card_sets_redeemed: u32,
     turn_order: [PlayerId; 5],
     recording: Vec<Record>,
+    /// Shuffles the turn order and the deck. The dice are rolled separately, so that the cards
+    /// drawn do not depend on the number of attacks.
+    rng: StdRng,
+    dice_rng: StdRng,
 }
 
 impl Default for EngineState {
@@ -29,6 +34,13 @@ impl Default for EngineState {
 
 impl EngineState {
     pub fn new() -> Self {
+        Self::with_seed(rand::random())
+    }
+
+    /// A game that plays out the same way with the same seed and the same moves
+    pub fn with_seed(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let dice_rng = StdRng::seed_from_u64(rand::Rng::gen(&mut rng));
         Self {
             deck: Vec::new(),
             discarded_deck: Vec::from(Card::all()),
@@ -43,6 +55,8 @@ impl EngineState {
                 PlayerId::P4,
             ],
             recording: Vec::new(),
+            rng,
+            dice_rng,
         }
     }
 
@@ -62,13 +76,31 @@ impl EngineState {
         &self.players
     }
 
+    pub fn card_sets_redeemed(&self) -> u32 {
+        self.card_sets_redeemed
+    }
+
     fn draw_card(&mut self) -> Card {
         self.deck
             .pop()
             .expect("Need to shuffle deck before drawing")
     }
 
+    pub(crate) fn rng(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+
     pub fn deck(&self) -> &[Card] {
         &self.deck
     }
+
+    /// The cards that have been redeemed since the deck was last shuffled
+    pub fn discarded_deck(&self) -> &[Card] {
+        &self.discarded_deck
+    }
+
+    /// Replaces the deck after it has been shuffled, which must have the same cards
+    pub(crate) fn set_deck(&mut self, deck: Vec<Card>) {
+        self.deck = deck;
+    }
 }
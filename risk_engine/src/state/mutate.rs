@@ -3,6 +3,7 @@ use std::collections::HashSet;
 use rand::prelude::SliceRandom;
 
 use risk_shared::{
+    calculate_set_bonus,
     map::TerritoryId,
     player::PlayerId,
     record::{
@@ -85,14 +86,6 @@ impl EngineState {
     }
 
     fn commit_move_redeem_cards(&mut self, player: PlayerId, r: MoveRedeemCards) {
-        fn calculate_set_bonus(x: u32) -> u32 {
-            const FIXED_VALUES: [u32; 6] = [4, 6, 8, 10, 12, 15];
-            FIXED_VALUES
-                .get(x as usize)
-                .copied()
-                .unwrap_or_else(|| 15 + (x - FIXED_VALUES.len() as u32 + 1) * 5)
-        }
-
         let total_set_bonus = {
             let mut set_bonus = 0;
             for _ in 0..r.sets.len() {
@@ -167,6 +160,7 @@ impl EngineState {
 
     fn commit_record_player_eliminated(&mut self, r: PlayerEliminated) {
         self.players[r.player].alive = false;
+        self.players[r.player].cards.clear();
 
         let Record::Attack(attack) = &self.recording[r.record_attack_id] else {
             unreachable!();
@@ -187,7 +181,7 @@ impl EngineState {
         );
 
         self.deck = std::mem::take(&mut self.discarded_deck);
-        self.deck.shuffle(&mut rand::thread_rng());
+        self.deck.shuffle(&mut self.rng);
     }
 
     fn commit_record_start_game(&mut self, r: StartGame) {
@@ -7,7 +7,7 @@ use risk_shared::{
 
 use super::EngineState;
 
-pub fn attack(state: &EngineState, move_attack_id: usize, move_defend_id: usize) -> Attack {
+pub fn attack(state: &mut EngineState, move_attack_id: usize, move_defend_id: usize) -> Attack {
     let Record::Move(_, Move::Attack(move_attack)) = &state.recording[move_attack_id] else {
         unreachable!()
     };
@@ -22,8 +22,8 @@ pub fn attack(state: &EngineState, move_attack_id: usize, move_defend_id: usize)
 
     let defending_troops = move_defend.defending_troops;
 
-    let attacking_rolls = roll_sorted(attacking_troops);
-    let defending_rolls = roll_sorted(defending_troops);
+    let attacking_rolls = roll_sorted(&mut state.dice_rng, attacking_troops);
+    let defending_rolls = roll_sorted(&mut state.dice_rng, defending_troops);
 
     let attacking_lost = attacking_rolls
         .iter()
@@ -101,8 +101,8 @@ pub fn drew_card(state: &mut EngineState, player: PlayerId) -> DrewCard {
     }
 }
 
-fn roll_sorted(count: u32) -> Vec<u32> {
-    let mut attacking_roles = std::iter::repeat_with(|| rand::thread_rng().gen_range(1..=6))
+fn roll_sorted(rng: &mut impl Rng, count: u32) -> Vec<u32> {
+    let mut attacking_roles = std::iter::repeat_with(|| rng.gen_range(1..=6))
         .take(count as usize)
         .collect::<Vec<_>>();
 
@@ -5,7 +5,7 @@ use risk_shared::record::{
     MoveRedeemCards, MoveTroopsAfterAttack, PlayerEliminated, PublicPlayerEliminated, PublicRecord,
     PublicStartGame, RedeemedCards, StartTurn, TerritoryConquered,
 };
-use risk_shared::{map::TerritoryId, player::PlayerId, Card};
+use risk_shared::{calculate_set_bonus, map::TerritoryId, player::PlayerId, Card};
 
 use super::ClientState;
 
@@ -100,14 +100,6 @@ impl ClientState {
     }
 
     fn commit_move_redeem_cards(&mut self, player: PlayerId, r: MoveRedeemCards) {
-        fn calculate_set_bonus(x: u32) -> u32 {
-            const FIXED_VALUES: [u32; 6] = [4, 6, 8, 10, 12, 15];
-            FIXED_VALUES
-                .get(x as usize)
-                .copied()
-                .unwrap_or_else(|| 15 + (x - FIXED_VALUES.len() as u32 + 1) * 5)
-        }
-
         let total_set_bonus = {
             let mut set_bonus = 0;
             for _ in 0..r.sets.len() {
@@ -206,7 +198,8 @@ impl ClientState {
         };
 
         assert_eq!(*player, self.me.id);
-        self.me.cards.extend(r.cards_surrendered)
+        self.me.cards.extend(r.cards_surrendered);
+        self.players[r.player].card_count = 0;
     }
 
     fn commit_public_record_player_eliminated(&mut self, r: PublicPlayerEliminated) {
@@ -222,7 +215,8 @@ impl ClientState {
         };
 
         assert_ne!(*player, self.me.id);
-        self.players[*player].card_count += self.players[r.player].card_count;
+        self.players[*player].card_count += r.cards_surrendered_count;
+        self.players[r.player].card_count = 0;
     }
 
     fn commit_record_redeemed_cards(&mut self, _: RedeemedCards) {}
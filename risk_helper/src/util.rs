@@ -1,3 +1,5 @@
+use std::collections::VecDeque;
+
 use enum_map::EnumMap;
 use risk_shared::{
     map::{TerritoryId, EDGES},
@@ -96,3 +98,27 @@ pub fn adjacent_territories(territories: &[TerritoryId]) -> Vec<TerritoryId> {
 
     adjacent
 }
+
+/// The territories that can be reached from `source` through `territories`, which troops can be
+/// fortified into if they are the territories owned by the player
+pub fn connected_territories(territories: &[TerritoryId], source: TerritoryId) -> Vec<TerritoryId> {
+    let mut included = EnumMap::from_array([false; 42]);
+    for &territory in territories {
+        included[territory] = true;
+    }
+
+    included[source] = false;
+    let mut queue = VecDeque::from([source]);
+    let mut connected = Vec::new();
+    while let Some(territory) = queue.pop_front() {
+        for &territory in EDGES[territory] {
+            if included[territory] {
+                included[territory] = false;
+                connected.push(territory);
+                queue.push_back(territory);
+            }
+        }
+    }
+
+    connected
+}
@@ -39,6 +39,15 @@ impl Card {
     }
 }
 
+/// The troops for the next set redeemed, after the given number of sets have been redeemed
+pub fn calculate_set_bonus(card_sets_redeemed: u32) -> u32 {
+    const FIXED_VALUES: [u32; 6] = [4, 6, 8, 10, 12, 15];
+    FIXED_VALUES
+        .get(card_sets_redeemed as usize)
+        .copied()
+        .unwrap_or_else(|| 15 + (card_sets_redeemed - FIXED_VALUES.len() as u32 + 1) * 5)
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, enum_map::Enum)]
 pub enum CardSymbol {
     Infantry,
@@ -0,0 +1,105 @@
+use risk_bots::registry::{self, BotConfig};
+use risk_engine::book::{Book, Phase};
+
+use crate::{sprt::SprtParams, CreatePlayerBot};
+
+/// A test read from a JSON file, e.g.
+/// ```json
+/// {
+///     "dev": { "bot": "puct", "params": { "c_puct": 0.7 } },
+///     "base": { "bot": "puct" },
+///     "sprt": { "h0_elo": 0.0, "h1_elo": 5.0, "alpha": 0.05, "beta": 0.05, "statistic": "finish" },
+///     "book": "book.json",
+///     "output": "test.sprt"
+/// }
+/// ```
+#[derive(Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SprtConfig {
+    pub dev: BotConfig,
+    pub base: BotConfig,
+    /// The bots in the other three seats
+    #[serde(default = "registry::default_opponents")]
+    pub opponents: Vec<BotConfig>,
+    pub sprt: SprtParams,
+    /// The openings that pairs of games start from, which are played from scratch without a book
+    #[serde(default)]
+    pub book: Option<String>,
+    /// The number of pairs of games played between each check of the LLR
+    #[serde(default = "default_batch_size")]
+    pub batch_size: u64,
+    /// The number of games played at once, which defaults to the number of threads available
+    #[serde(default)]
+    pub threads: Option<usize>,
+    pub output: String,
+}
+
+fn default_batch_size() -> u64 {
+    4
+}
+
+impl SprtConfig {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let contents =
+            std::fs::read_to_string(path).map_err(|e| format!("Cannot read {path}: {e}"))?;
+        let config = serde_json::from_str::<Self>(&contents)
+            .map_err(|e| format!("Cannot parse {path}: {e}"))?;
+
+        if config.opponents.is_empty() {
+            return Err(format!("{path} has no opponents"));
+        }
+
+        for bot in [&config.dev, &config.base]
+            .into_iter()
+            .chain(&config.opponents)
+        {
+            bot.validate()?;
+        }
+
+        Ok(config)
+    }
+
+    pub fn book(&self) -> Result<Option<Book>, String> {
+        self.book.as_deref().map(Book::load).transpose()
+    }
+}
+
+/// Generates a book of openings by playing the bot in every seat, e.g.
+/// ```json
+/// { "bot": { "bot": "puct" }, "phase": "placement", "openings": 1000, "output": "book.json" }
+/// ```
+#[derive(Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BookConfig {
+    pub bot: BotConfig,
+    #[serde(default)]
+    pub phase: Phase,
+    pub openings: usize,
+    #[serde(default)]
+    pub threads: Option<usize>,
+    pub output: String,
+}
+
+impl BookConfig {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let contents =
+            std::fs::read_to_string(path).map_err(|e| format!("Cannot read {path}: {e}"))?;
+        let config = serde_json::from_str::<Self>(&contents)
+            .map_err(|e| format!("Cannot parse {path}: {e}"))?;
+
+        config.bot.validate()?;
+        Ok(config)
+    }
+}
+
+impl CreatePlayerBot for BotConfig {
+    type Bot = Box<dyn risk_shared::player::PlayerBot>;
+
+    fn create(&self) -> Self::Bot {
+        BotConfig::create(self)
+    }
+
+    fn id(&self) -> String {
+        BotConfig::id(self)
+    }
+}
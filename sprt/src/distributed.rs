@@ -0,0 +1,348 @@
+//! Plays games on worker processes connected over TCP, so that a test or tuning run can use
+//! several machines. Each message is a JSON object on its own line.
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    sync::{Arc, Condvar, Mutex},
+};
+
+use risk_bots::registry::BotConfig;
+use risk_engine::book::{Book, Opening};
+use spsa::{config::SpsaConfig, tuner::Games};
+
+use crate::sprt::{self, SprtResult, Statistic};
+
+/// Pairs of games between two bots, which a worker plays and reports as an `SprtResult`
+#[derive(Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Batch {
+    pub dev: BotConfig,
+    pub base: BotConfig,
+    pub opponents: Vec<BotConfig>,
+    pub statistic: Statistic,
+    pub pairs: u64,
+    /// The opening of each pair, or none to play the whole game
+    #[serde(default)]
+    pub openings: Vec<Opening>,
+}
+
+/// Sent from the coordinator to a worker, which replies to each batch with its result
+#[derive(serde::Serialize, serde::Deserialize)]
+enum Message {
+    Batch(Batch),
+    Done,
+}
+
+impl Batch {
+    /// Draws an opening for each pair from the book if there is one
+    pub fn new(
+        dev: BotConfig,
+        base: BotConfig,
+        opponents: Vec<BotConfig>,
+        statistic: Statistic,
+        pairs: u64,
+        book: Option<&Book>,
+    ) -> Self {
+        let openings = book.map_or_else(Vec::new, |book| {
+            let mut rng = rand::thread_rng();
+            (0..pairs).map(|_| book.choose(&mut rng).clone()).collect()
+        });
+
+        Self {
+            dev,
+            base,
+            opponents,
+            statistic,
+            pairs,
+            openings,
+        }
+    }
+
+    pub fn play(&self) -> SprtResult {
+        let mut pairs = [0; 5];
+        for i in 0..self.pairs {
+            let opening = self.openings.get(i as usize);
+            let result = sprt::play_pair(
+                &self.dev,
+                &self.base,
+                &self.opponents,
+                self.statistic,
+                opening,
+            );
+            pairs[result] += 1;
+        }
+
+        SprtResult { pairs }
+    }
+}
+
+/// Hands out batches to the workers that connect and collects their results. A batch is given to
+/// another worker if its worker disconnects. Only one test or tuning run is served at a time.
+pub struct Coordinator {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    next_id: u64,
+    /// The batches that have not been sent to a worker
+    queue: VecDeque<(u64, Batch)>,
+    /// The batches that have been queued and whose results have not been collected
+    expected: HashSet<u64>,
+    results: HashMap<u64, SprtResult>,
+    done: bool,
+}
+
+impl State {
+    fn push(&mut self, batch: Batch) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.queue.push_back((id, batch));
+        self.expected.insert(id);
+        id
+    }
+
+    fn collect(&mut self, id: u64) -> SprtResult {
+        self.expected.remove(&id);
+        self.results.remove(&id).unwrap()
+    }
+}
+
+impl Coordinator {
+    /// Listens for workers on `address` in the background
+    pub fn bind(address: impl ToSocketAddrs) -> io::Result<Arc<Self>> {
+        let listener = TcpListener::bind(address)?;
+        let coordinator = Arc::new(Self {
+            state: Mutex::default(),
+            changed: Condvar::new(),
+        });
+
+        let accepting = coordinator.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        eprintln!("Cannot accept a worker: {e}");
+                        continue;
+                    }
+                };
+
+                let coordinator = accepting.clone();
+                std::thread::spawn(move || {
+                    let peer = stream
+                        .peer_addr()
+                        .map_or_else(|_| "unknown".to_owned(), |x| x.to_string());
+                    println!("Worker connected from {peer}");
+
+                    if let Err(e) = coordinator.serve(stream) {
+                        eprintln!("Lost the worker at {peer}: {e}");
+                    }
+                });
+            }
+        });
+
+        Ok(coordinator)
+    }
+
+    /// Plays every batch, and returns their results in order
+    pub fn play(&self, batches: Vec<Batch>) -> Vec<SprtResult> {
+        let mut state = self.state.lock().unwrap();
+        let ids = batches
+            .into_iter()
+            .map(|batch| state.push(batch))
+            .collect::<Vec<_>>();
+        self.changed.notify_all();
+
+        let mut state = self
+            .changed
+            .wait_while(state, |state| {
+                !ids.iter().all(|id| state.results.contains_key(id))
+            })
+            .unwrap();
+        ids.into_iter().map(|id| state.collect(id)).collect()
+    }
+
+    /// Returns the result of the next batch to finish, keeping a batch from `next_batch` queued so
+    /// that no worker waits for the coordinator
+    pub fn play_next(&self, mut next_batch: impl FnMut() -> Batch) -> SprtResult {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if let Some(&id) = state.results.keys().next() {
+                return state.collect(id);
+            }
+
+            if state.queue.is_empty() {
+                state.push(next_batch());
+                self.changed.notify_all();
+            }
+
+            state = self.changed.wait(state).unwrap();
+        }
+    }
+
+    /// Tells each worker to stop once it has finished its batch
+    pub fn finish(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.done = true;
+        state.queue.clear();
+        state.expected.clear();
+        self.changed.notify_all();
+    }
+
+    fn serve(&self, stream: TcpStream) -> io::Result<()> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut writer = stream;
+        loop {
+            let state = self.state.lock().unwrap();
+            let mut state = self
+                .changed
+                .wait_while(state, |state| state.queue.is_empty() && !state.done)
+                .unwrap();
+
+            let Some((id, batch)) = state.queue.pop_front() else {
+                drop(state);
+                return send(&mut writer, &Message::Done);
+            };
+
+            drop(state);
+            self.changed.notify_all();
+
+            let result = send(&mut writer, &Message::Batch(batch.clone()))
+                .and_then(|()| receive::<SprtResult>(&mut reader));
+
+            let mut state = self.state.lock().unwrap();
+            match result {
+                Ok(result) => {
+                    // The batch may belong to a run that has finished
+                    if state.expected.contains(&id) {
+                        state.results.insert(id, result);
+                    }
+                }
+                Err(e) => {
+                    if state.expected.contains(&id) {
+                        state.queue.push_front((id, batch));
+                    }
+
+                    drop(state);
+                    self.changed.notify_all();
+                    return Err(e);
+                }
+            }
+
+            drop(state);
+            self.changed.notify_all();
+        }
+    }
+}
+
+/// Plays the games of a tuning run on the workers. Games are played in pairs with the same seed,
+/// as in a test, and each pair is a separate batch so that it can be given to any worker.
+pub struct DistributedGames<'a> {
+    coordinator: &'a Coordinator,
+    config: &'a SpsaConfig,
+    book: Option<&'a Book>,
+}
+
+impl<'a> DistributedGames<'a> {
+    pub fn new(
+        coordinator: &'a Coordinator,
+        config: &'a SpsaConfig,
+        book: Option<&'a Book>,
+    ) -> Self {
+        Self {
+            coordinator,
+            config,
+            book,
+        }
+    }
+}
+
+impl Games for DistributedGames<'_> {
+    fn play_games(
+        &self,
+        candidates: &[HashMap<String, f64>],
+        reference: &HashMap<String, f64>,
+        games_per: u64,
+    ) -> Vec<f64> {
+        // An odd number of games is rounded up to whole pairs, and the score is scaled back down
+        let pairs = games_per.div_ceil(2);
+        let scale = games_per as f64 / (2 * pairs) as f64;
+
+        let base = &self.config.bot_with(reference);
+        let batches = candidates
+            .iter()
+            .flat_map(|candidate| {
+                let dev = self.config.bot_with(candidate);
+                (0..pairs).map(move |_| {
+                    Batch::new(
+                        dev.clone(),
+                        base.clone(),
+                        self.config.opponents.clone(),
+                        self.config.statistic,
+                        1,
+                        self.book,
+                    )
+                })
+            })
+            .collect();
+
+        let results = self.coordinator.play(batches);
+        results
+            .chunks(pairs as usize)
+            .map(|x| {
+                // A pair scores from -2 to 2, as two games do when played locally
+                let score = x
+                    .iter()
+                    .flat_map(|result| result.pairs.iter().enumerate())
+                    .map(|(i, &count)| (i as f64 - 2.0) * count as f64)
+                    .sum::<f64>();
+                score * scale
+            })
+            .collect()
+    }
+}
+
+/// Plays the batches sent by the coordinator at `address` until it has no more, with a separate
+/// connection for each thread
+pub fn work(address: &str, threads: usize) -> io::Result<()> {
+    std::thread::scope(|scope| {
+        let connections = (0..threads)
+            .map(|_| scope.spawn(|| work_connection(address)))
+            .collect::<Vec<_>>();
+
+        connections.into_iter().try_for_each(|x| x.join().unwrap())
+    })
+}
+
+fn work_connection(address: &str) -> io::Result<()> {
+    let stream = TcpStream::connect(address)?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+    loop {
+        match receive::<Message>(&mut reader) {
+            Ok(Message::Batch(batch)) => send(&mut writer, &batch.play())?,
+            Ok(Message::Done) => return Ok(()),
+            // The coordinator can exit before telling every worker that it is done
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn send<T: serde::Serialize>(writer: &mut impl Write, message: &T) -> io::Result<()> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    writer.write_all(line.as_bytes())
+}
+
+fn receive<T: serde::de::DeserializeOwned>(reader: &mut impl BufRead) -> io::Result<T> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+
+    Ok(serde_json::from_str(&line)?)
+}
@@ -0,0 +1,386 @@
+//! Plays seeded games with random bots and checks the engine after every record, so that rules
+//! the other bots rarely reach, such as redeeming the cards captured from an eliminated player,
+//! are exercised
+
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    panic::{self, AssertUnwindSafe},
+    rc::Rc,
+};
+
+use attack_game::cards::is_card_set;
+use enum_map::EnumMap;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use risk_bots::{complex::ComplexExample, random::RandomBot};
+use risk_engine::{
+    censor,
+    game_engine::{GameEngine, GameResult},
+    player::PlayerConnection,
+    state::EngineState,
+    NUM_STARTING_TROOPS,
+};
+use risk_helper::{state::ClientState, util, ManagedPlayerBot};
+use risk_shared::{
+    map::{TerritoryId, EDGES},
+    player::{PlayerBot, PlayerId},
+    record::{Cause, Move, PublicRecord, Record},
+    CARD_COUNT, NUM_PLAYERS,
+};
+
+#[derive(Default, Debug)]
+pub struct FuzzResult {
+    pub finished: u64,
+    /// Games that reached the maximum length of a recording
+    pub cancelled: u64,
+    /// The number of times a player redeemed cards after eliminating another player
+    pub captured_redemptions: u64,
+    /// The seeds of the games that failed a check, which replay the same game
+    pub failed: Vec<u64>,
+}
+
+/// Plays `games` games with consecutive seeds from `first_seed`
+pub fn fuzz(games: u64, first_seed: u64) -> FuzzResult {
+    (first_seed..first_seed + games)
+        .into_par_iter()
+        .map(|seed| {
+            let mut result = FuzzResult::default();
+            match panic::catch_unwind(AssertUnwindSafe(|| play_game(seed))) {
+                Ok((game_result, captured_redemptions)) => {
+                    match game_result {
+                        GameResult::Success(_) => result.finished += 1,
+                        GameResult::Cancelled => result.cancelled += 1,
+                    }
+                    result.captured_redemptions += captured_redemptions;
+                }
+                Err(_) => {
+                    eprintln!("Game {seed} failed");
+                    result.failed.push(seed);
+                }
+            }
+
+            result
+        })
+        .reduce(FuzzResult::default, |mut x, y| {
+            x.finished += y.finished;
+            x.cancelled += y.cancelled;
+            x.captured_redemptions += y.captured_redemptions;
+            x.failed.extend(y.failed);
+            x
+        })
+}
+
+/// Plays a game which panics if the engine fails a check, and returns the result and the number
+/// of captured redemptions. Games between random bots alone almost never end, so each seat is
+/// given to either a random bot or the complex example, which eliminates players.
+pub fn play_game(seed: u64) -> (GameResult, u64) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let players = EnumMap::from_fn(|player| {
+        let bot_rng = StdRng::seed_from_u64(rng.gen());
+        let bot: Box<dyn PlayerBot> = if rng.gen_bool(0.5) {
+            Box::new(ManagedPlayerBot::new(RandomBot::new(bot_rng)))
+        } else {
+            Box::new(ManagedPlayerBot::new(ComplexExample::new(bot_rng)))
+        };
+        PlayerConnection::new(bot, player)
+    });
+
+    let checker = Rc::new(RefCell::new(Checker::new()));
+    let mut game = GameEngine::with_seed(players, seed);
+    let inspector = checker.clone();
+    game.set_inspector(move |state| inspector.borrow_mut().check(state));
+
+    let result = game.start();
+    let captured_redemptions = checker.borrow().captured_redemptions;
+    (result, captured_redemptions)
+}
+
+/// Follows the game alongside the engine, with the state that each player is given
+struct Checker {
+    clients: EnumMap<PlayerId, ClientState>,
+    /// The number of records that have been checked
+    checked: usize,
+    /// The troops that should be on the board or waiting to be placed
+    troops: u32,
+    /// The territory that has just been conquered, which has no troops until some are moved in
+    conquered: Option<TerritoryId>,
+    captured_redemptions: u64,
+}
+
+impl Checker {
+    fn new() -> Self {
+        Self {
+            clients: EnumMap::from_fn(|_| ClientState::new()),
+            checked: 0,
+            troops: NUM_STARTING_TROOPS * NUM_PLAYERS as u32,
+            conquered: None,
+            captured_redemptions: 0,
+        }
+    }
+
+    fn check(&mut self, state: &EngineState) {
+        let recording = state.recording();
+        for (i, record) in recording.iter().enumerate().skip(self.checked) {
+            self.follow(state, i, record);
+        }
+        self.checked = recording.len();
+
+        let last = recording.len() - 1;
+        self.check_troops(state, last);
+        self.check_cards(state, last);
+        self.check_clients(state, last);
+    }
+
+    fn follow(&mut self, state: &EngineState, i: usize, record: &Record) {
+        if let Record::Move(player, mov) = record {
+            self.check_move(i, *player, mov);
+        }
+
+        match record {
+            Record::StartTurn(r) => self.troops += r.territory_bonus + r.continent_bonus,
+            Record::RedeemedCards(r) => {
+                self.troops += r.total_set_bonus + r.matching_territory_bonus
+            }
+            Record::Attack(r) => {
+                self.troops -= r.attacking_lost + r.defending_lost;
+                if r.territory_conquered {
+                    let Record::Move(_, Move::Attack(move_attack)) =
+                        &state.recording()[r.move_attack_id]
+                    else {
+                        unreachable!();
+                    };
+
+                    self.conquered = Some(move_attack.defending_territory);
+                }
+            }
+            Record::Move(_, Move::MoveTroopsAfterAttack(_)) => self.conquered = None,
+            Record::Move(_, Move::RedeemCards(r))
+                if matches!(r.cause, Cause::PlayerEliminated) && !r.sets.is_empty() =>
+            {
+                self.captured_redemptions += 1;
+            }
+            _ => {}
+        }
+
+        // The client state cannot commit the winner, and is not queried after the game ends
+        if matches!(record, Record::Winner(_)) {
+            return;
+        }
+
+        for (player, client) in &mut self.clients {
+            client.commit(i, censor::censor(state, record.clone(), player));
+        }
+    }
+
+    /// The move is legal in the state of the player that made it, which has not yet seen the move
+    fn check_move(&self, i: usize, player: PlayerId, mov: &Move) {
+        let client = &self.clients[player];
+        let territories = client.territories();
+        let owned = |x: TerritoryId| territories[x].occupier == Some(player);
+
+        match mov {
+            Move::ClaimTerritory(territory) => assert!(
+                territories[*territory].occupier.is_none(),
+                "{player:?} claimed the occupied {territory:?} in record {i}"
+            ),
+            Move::PlaceInitialTroop(territory) => assert!(
+                owned(*territory) && client.players()[player].troops_remaining > 0,
+                "{player:?} cannot place an initial troop on {territory:?} in record {i}"
+            ),
+            Move::RedeemCards(r) => {
+                let cards = &client.me().cards;
+                let redeemed = r.sets.iter().flatten().collect::<HashSet<_>>();
+                assert!(
+                    redeemed.len() == 3 * r.sets.len()
+                        && redeemed.iter().all(|x| cards.contains(x))
+                        && r.sets.iter().all(|&x| is_card_set(x)),
+                    "{player:?} redeemed invalid sets in record {i}"
+                );
+
+                // Sets must be redeemed until fewer than 5 cards are held, and after eliminating a
+                // player no more than that
+                let remaining = cards.len() - redeemed.len();
+                assert!(
+                    remaining < 5 && (matches!(r.cause, Cause::TurnStarted) || remaining + 3 >= 5),
+                    "{player:?} redeemed {} sets of {} cards in record {i}",
+                    r.sets.len(),
+                    cards.len()
+                );
+            }
+            Move::DistributeTroops(r) => {
+                let me = &client.players()[player];
+                let total = r.distributions.values().sum::<u32>();
+                assert!(
+                    total == me.troops_remaining
+                        && r.distributions.iter().all(|(id, &x)| x == 0 || owned(id)),
+                    "{player:?} distributed troops it does not have in record {i}"
+                );
+                assert!(
+                    me.must_place_territory_bonus.is_empty()
+                        || me
+                            .must_place_territory_bonus
+                            .iter()
+                            .any(|&x| r.distributions[x] >= 2),
+                    "{player:?} did not place the matching territory bonus in record {i}"
+                );
+            }
+            Move::Attack(r) => {
+                let troops = territories[r.attacking_territory].troops;
+                assert!(
+                    owned(r.attacking_territory)
+                        && !owned(r.defending_territory)
+                        && territories[r.defending_territory].occupier.is_some()
+                        && EDGES[r.attacking_territory].contains(&r.defending_territory)
+                        && (1..=std::cmp::min(3, troops.saturating_sub(1)))
+                            .contains(&r.attacking_troops),
+                    "{player:?} made an illegal attack in record {i}"
+                );
+            }
+            Move::Defend(r) => {
+                let PublicRecord::Move(_, Move::Attack(move_attack)) =
+                    &client.recording()[r.move_attack_id]
+                else {
+                    panic!(
+                        "{player:?} defended against a record that is not an attack in record {i}"
+                    );
+                };
+
+                let troops = territories[move_attack.defending_territory].troops;
+                assert!(
+                    owned(move_attack.defending_territory)
+                        && (1..=std::cmp::min(2, troops)).contains(&r.defending_troops),
+                    "{player:?} made an illegal defence in record {i}"
+                );
+            }
+            Move::MoveTroopsAfterAttack(r) => {
+                let PublicRecord::Attack(record_attack) = &client.recording()[r.record_attack_id]
+                else {
+                    panic!("{player:?} moved troops after a record that is not an attack in record {i}");
+                };
+                let PublicRecord::Move(_, Move::Attack(move_attack)) =
+                    &client.recording()[record_attack.move_attack_id]
+                else {
+                    unreachable!();
+                };
+
+                // At least as many troops as attacked with must be moved in, unless fewer are left
+                let available = territories[move_attack.attacking_territory].troops - 1;
+                let min_troops = std::cmp::min(move_attack.attacking_troops, available);
+                assert!(
+                    record_attack.territory_conquered
+                        && (min_troops..=available).contains(&r.troop_count),
+                    "{player:?} moved {} troops after attack in record {i}",
+                    r.troop_count
+                );
+            }
+            Move::Fortify(r) => {
+                let owned_territories = client.territories_owned_by(Some(player));
+                assert!(
+                    owned(r.source_territory)
+                        && util::connected_territories(&owned_territories, r.source_territory)
+                            .contains(&r.target_territory)
+                        && (1..territories[r.source_territory].troops).contains(&r.troop_count),
+                    "{player:?} made an illegal fortify in record {i}"
+                );
+            }
+            Move::AttackPass | Move::FortifyPass => {}
+        }
+    }
+
+    fn check_troops(&self, state: &EngineState, i: usize) {
+        let on_board = state.territories().values().map(|x| x.troops).sum::<u32>();
+        let remaining = state
+            .players()
+            .values()
+            .map(|x| x.troops_remaining)
+            .sum::<u32>();
+        assert_eq!(
+            on_board + remaining,
+            self.troops,
+            "Troops are not conserved after record {i}"
+        );
+
+        for (id, territory) in state.territories() {
+            if territory.occupier.is_none() {
+                assert_eq!(territory.troops, 0, "{id:?} is unoccupied with troops");
+            } else if self.conquered != Some(id) {
+                assert!(
+                    territory.troops > 0,
+                    "{id:?} has no troops after record {i}"
+                );
+            }
+        }
+    }
+
+    /// Every card is in the deck, the discarded cards or a player's hand exactly once
+    fn check_cards(&self, state: &EngineState, i: usize) {
+        let mut cards = state
+            .deck()
+            .iter()
+            .chain(state.discarded_deck())
+            .chain(state.players().values().flat_map(|x| &x.cards))
+            .map(|x| x.id())
+            .collect::<Vec<_>>();
+        cards.sort_unstable();
+
+        let expected = (0..CARD_COUNT as u8).collect::<Vec<_>>();
+        assert_eq!(
+            cards, expected,
+            "The cards are not all accounted for after record {i}"
+        );
+    }
+
+    /// The state of each player that is still in the game agrees with the engine
+    fn check_clients(&self, state: &EngineState, i: usize) {
+        for (player, client) in &self.clients {
+            if !state.players()[player].alive {
+                continue;
+            }
+
+            for (id, territory) in state.territories() {
+                let seen = &client.territories()[id];
+                assert!(
+                    seen.occupier == territory.occupier && seen.troops == territory.troops,
+                    "{player:?} disagrees about {id:?} after record {i}"
+                );
+            }
+
+            for (id, other) in state.players() {
+                let seen = &client.players()[id];
+                let card_count = if id == player {
+                    client.me().cards.len()
+                } else {
+                    seen.card_count
+                };
+
+                assert!(
+                    seen.alive == other.alive
+                        && seen.troops_remaining == other.troops_remaining
+                        && card_count == other.cards.len()
+                        && seen
+                            .must_place_territory_bonus
+                            .iter()
+                            .collect::<HashSet<_>>()
+                            == other.must_place_territory_bonus.iter().collect(),
+                    "{player:?} disagrees about {id:?} after record {i}"
+                );
+            }
+
+            let mut cards = client.me().cards.clone();
+            let mut expected = state.players()[player].cards.clone();
+            cards.sort_unstable_by_key(|x| x.id());
+            expected.sort_unstable_by_key(|x| x.id());
+            assert_eq!(
+                cards, expected,
+                "{player:?} disagrees about its cards after record {i}"
+            );
+
+            assert_eq!(
+                client.card_sets_redeemed(),
+                state.card_sets_redeemed(),
+                "{player:?} disagrees about the sets redeemed after record {i}"
+            );
+        }
+    }
+}
@@ -1,3 +1,6 @@
+pub mod config;
+pub mod distributed;
+pub mod fuzz;
 pub mod sprt;
 
 use risk_shared::player::PlayerBot;
@@ -6,4 +9,8 @@ pub trait CreatePlayerBot {
     type Bot: PlayerBot;
 
     fn create(&self) -> Self::Bot;
+
+    /// Identifies the bot in saved results, so that a run is only resumed with the same bots. This
+    /// must include any parameters of the bot.
+    fn id(&self) -> String;
 }
@@ -1,44 +1,82 @@
 use enum_map::EnumMap;
-use rand::{RngCore, SeedableRng};
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
-use risk_bots::{very_bad::VeryBad, very_bad13::VeryBad13};
+use risk_bots::registry::{self, BotConfig};
 use risk_engine::{
-    game_engine::{GameEngine, GameResult},
+    book::{Book, Opening},
+    game_engine::GameEngine,
     player::PlayerConnection,
 };
-use risk_helper::ManagedPlayerBot;
 use risk_shared::player::{PlayerBot, PlayerId};
 
 use crate::CreatePlayerBot;
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+pub use risk_engine::game_engine::Statistic;
+
+/// The count of each pair score that has not happened yet when calculating the LLR
+const REGULARIZATION: f64 = 1e-3;
+
+/// The bounds of a test, which are in logistic Elo, i.e. the Elo difference that gives the
+/// expected score of the dev bot. These are not BayesElo or normalized Elo, so bounds taken from
+/// tests that use those must be converted first.
+#[derive(Clone, Copy, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
 pub struct SprtParams {
     pub h0_elo: f64,
     pub h1_elo: f64,
     pub alpha: f64,
     pub beta: f64,
+    #[serde(default)]
+    pub statistic: Statistic,
 }
 
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub struct Sprt {
+    params: SprtParams,
     h0_elo: f64,
     h1_elo: f64,
     a: f64,
     b: f64,
 }
 
+/// The results of pairs of games, where each pair is played with the same seed and the dev and base
+/// bots swap seats. Pairs are counted by the score of the dev bot in half points, from losing both
+/// games to winning both.
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, serde::Serialize, serde::Deserialize)]
 pub struct SprtResult {
-    pub results: [u64; 3],
+    pub pairs: [u64; 5],
+}
+
+/// The bots and openings that a test is played with, which must be the same to resume it
+#[derive(Clone, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize)]
+pub struct SprtGames {
+    pub dev: String,
+    pub base: String,
+    pub opponents: Vec<String>,
+    /// The path of the book, if the games start from one
+    pub book: Option<String>,
+}
+
+/// The contents of the file written during a run, which is used to resume it
+#[derive(Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+struct SavedSprt {
+    params: SprtParams,
+    #[serde(flatten)]
+    games: SprtGames,
+    results: SprtResult,
 }
 
 impl SprtResult {
+    pub fn num_pairs(self) -> u64 {
+        self.pairs.iter().sum::<u64>()
+    }
+
     pub fn num_games(self) -> u64 {
-        self.results.iter().sum::<u64>()
+        2 * self.num_pairs()
     }
 
     pub fn score(self) -> f64 {
-        (self.results[0] as f64 + 0.5 * self.results[1] as f64) / self.num_games() as f64
+        pair_scores(self.pairs.map(|x| x as f64))
+            .map(|(p, x)| p * x)
+            .sum::<f64>()
     }
 
     pub fn elo_diff(self) -> f64 {
@@ -46,36 +84,51 @@ impl SprtResult {
         -400.0 * ((1.0 - score) / score).log10()
     }
 
+    /// The log likelihood ratio of the logistic Elo bounds, using the normal approximation of the
+    /// pentanomial model
     pub fn llr(self, h0_elo: f64, h1_elo: f64) -> f64 {
-        if self.results[0] == 0 || self.results[2] == 0 {
+        if self.num_pairs() == 0 {
             return 0.0;
         }
 
-        let num_games = self.num_games();
-        let probs = self.results.map(|x| x as f64 / num_games as f64);
+        // Scores that have not happened yet are counted as a small fraction of a pair, so that the
+        // variance is never zero and a lopsided test still finishes
+        let counts = self.pairs.map(|x| match x {
+            0 => REGULARIZATION,
+            x => x as f64,
+        });
+        let num_pairs = counts.iter().sum::<f64>();
 
-        let draw_elo =
-            200.0 * (((1.0 - probs[0]) / probs[0]) * ((1.0 - probs[2]) / probs[2])).log10();
+        let score = pair_scores(counts).map(|(p, x)| p * x).sum::<f64>();
+        let variance = pair_scores(counts)
+            .map(|(p, x)| p * (x - score).powi(2))
+            .sum::<f64>();
 
-        let prob_h0 = elo_probs(h0_elo, draw_elo);
-        let prob_h1 = elo_probs(h1_elo, draw_elo);
+        let s0 = elo_score(h0_elo);
+        let s1 = elo_score(h1_elo);
+        num_pairs * (s1 - s0) * (2.0 * score - s0 - s1) / (2.0 * variance)
+    }
+}
 
-        (0..3)
-            .map(|x| {
-                // Avoid nans
-                if self.results[x] == 0 {
-                    0.0
-                } else {
-                    self.results[x] as f64 * (prob_h1[x] / prob_h0[x]).ln()
-                }
-            })
-            .sum::<f64>()
+impl SprtGames {
+    pub fn new<P1, P2>(dev: &P1, base: &P2, opponents: &[BotConfig], book: Option<&str>) -> Self
+    where
+        P1: CreatePlayerBot,
+        P2: CreatePlayerBot,
+    {
+        Self {
+            dev: dev.id(),
+            base: base.id(),
+            opponents: opponents.iter().map(BotConfig::id).collect(),
+            book: book.map(str::to_owned),
+        }
     }
 }
 
 impl Sprt {
     pub fn new(params: SprtParams) -> Self {
         Self {
+            params,
             h0_elo: params.h0_elo,
             h1_elo: params.h1_elo,
             a: (params.beta / (1.0 - params.alpha)).ln(),
@@ -83,49 +136,148 @@ impl Sprt {
         }
     }
 
-    pub fn sprt<P1, P2>(&self, p1: &P1, p2: &P2, batch_size: u64, write_file: &str) -> SprtResult
+    /// Plays `p1` against `p2`, with the other seats filled from `opponents`. Each pair of games
+    /// starts from an opening drawn from the book if there is one, which is given with its path.
+    pub fn sprt<P1, P2>(
+        &self,
+        p1: &P1,
+        p2: &P2,
+        opponents: &[BotConfig],
+        book: Option<(&str, &Book)>,
+        batch_size: u64,
+        write_file: &str,
+    ) -> Result<SprtResult, String>
     where
         P1: CreatePlayerBot + Sync + 'static,
         P2: CreatePlayerBot + Sync + 'static,
     {
-        let mut results = SprtResult { results: [0; 3] };
-
-        loop {
-            let match_ = (0..batch_size)
+        let games = SprtGames::new(p1, p2, opponents, book.map(|(path, _)| path));
+        self.run(games, batch_size, write_file, || {
+            let pairs = (0..batch_size)
                 .into_par_iter()
-                .map(|_| play_game(p1, p2))
-                .reduce(
-                    || [0; 3],
-                    |[x1, y1, z1], [x2, y2, z2]| [x1 + x2, y1 + y2, z1 + z2],
+                .map(|_| {
+                    let opening = book.map(|(_, x)| x.choose(&mut rand::thread_rng()));
+                    play_pair(p1, p2, opponents, self.params.statistic, opening)
+                })
+                .fold(
+                    || [0; 5],
+                    |mut pairs, x| {
+                        pairs[x] += 1;
+                        pairs
+                    },
+                )
+                .reduce(|| [0; 5], |x, y| std::array::from_fn(|i| x[i] + y[i]));
+
+            SprtResult { pairs }
+        })
+    }
+
+    /// Adds the results of `play_batch` until the test finishes, where `games` describes the games
+    /// being played. The results are saved to `write_file` after every batch, and a run is resumed
+    /// from it if it exists.
+    pub fn run(
+        &self,
+        games: SprtGames,
+        batch_size: u64,
+        write_file: &str,
+        mut play_batch: impl FnMut() -> SprtResult,
+    ) -> Result<SprtResult, String> {
+        let mut saved = SavedSprt {
+            params: self.params,
+            games,
+            results: SprtResult { pairs: [0; 5] },
+        };
+
+        let mut results = match std::fs::read_to_string(write_file) {
+            Ok(contents) => {
+                let previous = serde_json::from_str::<SavedSprt>(&contents)
+                    .map_err(|e| format!("Cannot resume from {write_file}: {e}"))?;
+
+                let mismatch = |what| Err(format!("Cannot resume from {write_file} with {what}"));
+                if previous.params != saved.params {
+                    return mismatch("different SPRT parameters");
+                }
+                if (&previous.games.dev, &previous.games.base)
+                    != (&saved.games.dev, &saved.games.base)
+                {
+                    return mismatch("different bots");
+                }
+                if previous.games.opponents != saved.games.opponents {
+                    return mismatch("different opponents");
+                }
+                if previous.games.book != saved.games.book {
+                    return mismatch("a different book");
+                }
+
+                println!(
+                    "Resuming from {write_file} after {} pairs",
+                    previous.results.num_pairs()
                 );
+                previous.results
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => saved.results,
+            Err(e) => return Err(format!("Cannot read {write_file}: {e}")),
+        };
 
-            results.results[0] += match_[0];
-            results.results[1] += match_[1];
-            results.results[2] += match_[2];
+        // A resumed run may have already finished
+        while (self.a..=self.b).contains(&results.llr(self.h0_elo, self.h1_elo)) {
+            let match_ = play_batch();
+            for (total, x) in results.pairs.iter_mut().zip(match_.pairs) {
+                *total += x;
+            }
 
-            if results.num_games() % (5 * batch_size) == 0 {
+            if results.num_pairs().is_multiple_of(5 * batch_size) {
                 println!(
-                    "{} Games: {:?} Score: {:.2}% Elo: {} LLR: {}",
-                    results.num_games(),
-                    results.results,
+                    "{} Pairs: {:?} Score: {:.2}% Elo: {} LLR: {}",
+                    results.num_pairs(),
+                    results.pairs,
                     results.score() * 100.0,
                     results.elo_diff(),
                     results.llr(self.h0_elo, self.h1_elo)
                 );
-
-                std::fs::write(write_file, serde_json::to_string(&results).unwrap()).unwrap()
             }
 
-            if !(self.a..=self.b).contains(&results.llr(self.h0_elo, self.h1_elo)) {
-                break;
-            }
+            // Saved after every batch, so that the batch which finishes the test is not replayed
+            saved.results = results;
+            std::fs::write(write_file, serde_json::to_string(&saved).unwrap())
+                .map_err(|e| format!("Cannot write {write_file}: {e}"))?;
         }
 
-        results
+        Ok(results)
     }
 }
 
-fn play_game<P1, P2>(p1: &P1, p2: &P2) -> [u64; 3]
+/// Plays a pair of games with the same seed, opening and opponents, with the dev and base bots
+/// swapping seats. Returns the score of the dev bot in half points.
+pub(crate) fn play_pair<P1, P2>(
+    p1: &P1,
+    p2: &P2,
+    opponents: &[BotConfig],
+    statistic: Statistic,
+    opening: Option<&Opening>,
+) -> usize
+where
+    P1: CreatePlayerBot + 'static,
+    P2: CreatePlayerBot + 'static,
+{
+    let seed = rand::random();
+    let opponents = registry::choose_opponents(opponents);
+
+    let first = play_game(p1, p2, opponents, seed, statistic, opening);
+    let second = 2 - play_game(p2, p1, opponents, seed, statistic, opening);
+    first + second
+}
+
+/// Plays a game between the bots in the first two seats, and returns the score of the first bot
+/// in half points
+fn play_game<P1, P2>(
+    p1: &P1,
+    p2: &P2,
+    opponents: [&BotConfig; 3],
+    seed: u64,
+    statistic: Statistic,
+    opening: Option<&Opening>,
+) -> usize
 where
     P1: CreatePlayerBot + 'static,
     P2: CreatePlayerBot + 'static,
@@ -134,31 +286,31 @@ where
         let bot = match player {
             PlayerId::P0 => Box::new(p1.create()) as Box<dyn PlayerBot>,
             PlayerId::P1 => Box::new(p2.create()) as Box<dyn PlayerBot>,
-            PlayerId::P2 => Box::new(ManagedPlayerBot::new(VeryBad::new())),
-            PlayerId::P3 => Box::new(ManagedPlayerBot::new(VeryBad13::new())),
-            PlayerId::P4 => {
-                let rng =
-                    rand_xoshiro::Xoshiro256StarStar::seed_from_u64(rand::thread_rng().next_u64());
-
-                let complex = risk_bots::complex::ComplexExample::new(rng);
-                Box::new(ManagedPlayerBot::new(complex)) as Box<dyn PlayerBot>
-            }
+            PlayerId::P2 => opponents[0].create(),
+            PlayerId::P3 => opponents[1].create(),
+            PlayerId::P4 => opponents[2].create(),
         };
         PlayerConnection::new(bot, player)
     });
 
-    let mut game = GameEngine::new(players);
+    let mut game = match opening {
+        Some(opening) => GameEngine::with_opening(players, seed, opening.clone()),
+        None => GameEngine::with_seed(players, seed),
+    };
 
-    match game.start() {
-        GameResult::Success(PlayerId::P0) => [1, 0, 0],
-        GameResult::Success(PlayerId::P1) => [0, 0, 1],
-        _ => [0, 1, 0],
-    }
+    let result = game.start();
+    game.score(&result, statistic)
 }
 
-fn elo_probs(elo: f64, draw_elo: f64) -> [f64; 3] {
-    let win = 1.0 / (1.0 + 10.0_f64.powf((-elo + draw_elo) / 400.0));
-    let loss = 1.0 / (1.0 + 10.0_f64.powf((elo + draw_elo) / 400.0));
+/// The proportion of pairs with each score, as a fraction of the two games
+fn pair_scores(counts: [f64; 5]) -> impl Iterator<Item = (f64, f64)> {
+    let num_pairs = counts.iter().sum::<f64>();
+    counts
+        .into_iter()
+        .enumerate()
+        .map(move |(i, x)| (x / num_pairs, i as f64 / 4.0))
+}
 
-    [win, 1.0 - win - loss, loss]
+fn elo_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10.0_f64.powf(-elo / 400.0))
 }
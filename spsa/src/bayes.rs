@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+
+use crate::{
+    spsa::{HyperParams, SpsaParam},
+    tuner::{self, Games, Tuner},
+};
+
+/// The length scale of the kernel, relative to the square root of the number of parameters
+const LENGTH_SCALE: f64 = 0.2;
+const SIGNAL_VARIANCE: f64 = 0.1;
+/// The variance of the result of a single game
+const GAME_VARIANCE: f64 = 0.5;
+/// Only the most recent observations are used, which bounds the cost of each step
+const MAX_OBSERVATIONS: usize = 256;
+const CANDIDATES: usize = 1024;
+/// The standard deviation of the candidates near the incumbent, as a proportion of each range
+const LOCAL_STEP: f64 = 0.05;
+/// The weight of the uncertainty in the upper confidence bound
+const EXPLORATION: f64 = 1.0;
+
+/// Bayesian optimization with a Gaussian process over the parameters scaled to the unit cube.
+/// Each step plays the candidate with the highest upper confidence bound against the starting
+/// parameters, so the objective does not move during tuning.
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct Bayes {
+    keys: Vec<String>,
+    params: HashMap<String, SpsaParam>,
+    hyper_params: HyperParams,
+    reference: HashMap<String, f64>,
+    /// Each scaled candidate and its mean score against the reference
+    observations: Vec<(Vec<f64>, f64)>,
+    curr_iteration: u64,
+}
+
+/// The posterior given the observations
+struct Posterior<'a> {
+    observations: &'a [(Vec<f64>, f64)],
+    cholesky: Vec<f64>,
+    alpha: Vec<f64>,
+    length_scale: f64,
+}
+
+impl<'a> Posterior<'a> {
+    fn new(observations: &'a [(Vec<f64>, f64)], length_scale: f64, noise: f64) -> Self {
+        let m = observations.len();
+        let mut covariance = vec![0.0; m * m];
+        for i in 0..m {
+            for j in 0..m {
+                covariance[i * m + j] =
+                    kernel(&observations[i].0, &observations[j].0, length_scale);
+            }
+            covariance[i * m + i] += noise;
+        }
+
+        let cholesky = cholesky(&covariance, m);
+        let y = observations.iter().map(|x| x.1).collect::<Vec<_>>();
+        let alpha = solve_upper(&cholesky, &solve_lower(&cholesky, &y), m);
+
+        Self {
+            observations,
+            cholesky,
+            alpha,
+            length_scale,
+        }
+    }
+
+    /// The mean and variance at the point
+    fn predict(&self, x: &[f64]) -> (f64, f64) {
+        let k = self
+            .observations
+            .iter()
+            .map(|(y, _)| kernel(x, y, self.length_scale))
+            .collect::<Vec<_>>();
+
+        let mean = k.iter().zip(&self.alpha).map(|(x, y)| x * y).sum::<f64>();
+        let v = solve_lower(&self.cholesky, &k);
+        let variance = SIGNAL_VARIANCE - v.iter().map(|x| x * x).sum::<f64>();
+        (mean, variance.max(0.0))
+    }
+}
+
+impl Bayes {
+    fn window(&self) -> &[(Vec<f64>, f64)] {
+        &self.observations[self.observations.len().saturating_sub(MAX_OBSERVATIONS)..]
+    }
+
+    fn posterior(&self) -> Posterior<'_> {
+        let length_scale = LENGTH_SCALE * (self.keys.len() as f64).sqrt();
+        let noise = GAME_VARIANCE / self.hyper_params.games_per() as f64;
+        Posterior::new(self.window(), length_scale, noise)
+    }
+
+    fn scale(&self, values: &HashMap<String, f64>) -> Vec<f64> {
+        self.keys
+            .iter()
+            .map(|key| {
+                let param = &self.params[key];
+                (values[key] - param.min) / (param.max - param.min)
+            })
+            .collect()
+    }
+
+    fn unscale(&self, x: &[f64]) -> HashMap<String, f64> {
+        let values = self
+            .keys
+            .iter()
+            .zip(x)
+            .map(|(key, x)| {
+                let param = &self.params[key];
+                param.min + x * (param.max - param.min)
+            })
+            .collect::<Vec<_>>();
+        tuner::to_values(&self.keys, &values)
+    }
+
+    /// The observed point with the highest posterior mean, or the reference if there are none
+    fn incumbent(&self, posterior: &Posterior) -> Vec<f64> {
+        posterior
+            .observations
+            .iter()
+            .map(|(x, _)| (x, posterior.predict(x).0))
+            .max_by(|x, y| x.1.total_cmp(&y.1))
+            .map_or_else(|| self.scale(&self.reference), |(x, _)| x.clone())
+    }
+}
+
+impl Tuner for Bayes {
+    fn new(params: HashMap<String, SpsaParam>, hyper_params: HyperParams) -> Self {
+        let reference = params
+            .iter()
+            .map(|(key, param)| (key.clone(), param.curr_value))
+            .collect();
+
+        Self {
+            keys: tuner::sorted_keys(&params),
+            params,
+            hyper_params,
+            reference,
+            observations: Vec::new(),
+            curr_iteration: 0,
+        }
+    }
+
+    fn values(&self) -> HashMap<String, f64> {
+        self.unscale(&self.incumbent(&self.posterior()))
+    }
+
+    fn curr_iteration(&self) -> u64 {
+        self.curr_iteration
+    }
+
+    fn num_iterations(&self) -> u64 {
+        self.hyper_params.num_iterations()
+    }
+
+    fn step<G, R>(&mut self, games: &G, rng: &mut R)
+    where
+        G: Games,
+        R: rand::Rng,
+    {
+        let posterior = self.posterior();
+        let incumbent = self.incumbent(&posterior);
+
+        // Half of the candidates are near the incumbent, and the rest are anywhere
+        let candidate = (0..CANDIDATES)
+            .map(|i| {
+                if i % 2 == 0 {
+                    incumbent
+                        .iter()
+                        .map(|x| (x + LOCAL_STEP * tuner::gen_normal(rng)).clamp(0.0, 1.0))
+                        .collect::<Vec<_>>()
+                } else {
+                    (0..self.keys.len()).map(|_| rng.gen::<f64>()).collect()
+                }
+            })
+            .map(|x| {
+                let (mean, variance) = posterior.predict(&x);
+                (x, mean + EXPLORATION * variance.sqrt())
+            })
+            .max_by(|x, y| x.1.total_cmp(&y.1))
+            .unwrap()
+            .0;
+
+        let games_per = self.hyper_params.games_per();
+        let score = games.play_games(&[self.unscale(&candidate)], &self.reference, games_per)[0];
+
+        self.observations
+            .push((candidate, score / games_per as f64));
+        self.curr_iteration += games_per;
+    }
+}
+
+fn kernel(x: &[f64], y: &[f64], length_scale: f64) -> f64 {
+    let distance = x.iter().zip(y).map(|(x, y)| (x - y).powi(2)).sum::<f64>();
+    SIGNAL_VARIANCE * (-distance / (2.0 * length_scale * length_scale)).exp()
+}
+
+/// The lower triangular factor of a positive definite matrix, stored by row
+fn cholesky(a: &[f64], n: usize) -> Vec<f64> {
+    let mut l = vec![0.0; n * n];
+    for i in 0..n {
+        for j in 0..=i {
+            let sum = (0..j).map(|k| l[i * n + k] * l[j * n + k]).sum::<f64>();
+            if i == j {
+                l[i * n + i] = (a[i * n + i] - sum).max(f64::EPSILON).sqrt();
+            } else {
+                l[i * n + j] = (a[i * n + j] - sum) / l[j * n + j];
+            }
+        }
+    }
+
+    l
+}
+
+/// Solves `L x = b`
+fn solve_lower(l: &[f64], b: &[f64]) -> Vec<f64> {
+    let n = b.len();
+    let mut x = vec![0.0; n];
+    for i in 0..n {
+        let sum = (0..i).map(|k| l[i * n + k] * x[k]).sum::<f64>();
+        x[i] = (b[i] - sum) / l[i * n + i];
+    }
+
+    x
+}
+
+/// Solves `L^T x = b`
+fn solve_upper(l: &[f64], b: &[f64], n: usize) -> Vec<f64> {
+    let mut x = vec![0.0; n];
+    for i in (0..n).rev() {
+        let sum = (i + 1..n).map(|k| l[k * n + i] * x[k]).sum::<f64>();
+        x[i] = (b[i] - sum) / l[i * n + i];
+    }
+
+    x
+}
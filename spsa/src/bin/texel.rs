@@ -0,0 +1,254 @@
+use std::io::{BufRead, BufReader};
+
+use attack_game::evaluate::{self, Eval, Features};
+use datagen::Sample;
+use rand::{seq::SliceRandom, SeedableRng};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+
+const DATA_FILE: &str = "data.jsonl";
+const VALIDATION_SPLIT: f64 = 0.1;
+const EPOCHS: usize = 2000;
+const REPORT_INTERVAL: usize = 50;
+
+const LEARNING_RATE: f64 = 0.005;
+const BETA1: f64 = 0.9;
+const BETA2: f64 = 0.999;
+const EPSILON: f64 = 1e-8;
+
+struct Position {
+    values: Vec<f64>,
+    card_sets_redeemed: u32,
+    outcome: f64,
+}
+
+fn main() {
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| DATA_FILE.to_owned());
+
+    let file = std::fs::File::open(&path).unwrap();
+    let mut positions = BufReader::new(file)
+        .lines()
+        .map(|line| {
+            let sample = serde_json::from_str::<Sample>(&line.unwrap()).unwrap();
+            let features = Features::new(&sample.game());
+            Position {
+                values: features.values().collect(),
+                card_sets_redeemed: features.card_sets_redeemed,
+                outcome: sample.outcome,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    positions.shuffle(&mut rand_xoshiro::Xoshiro256StarStar::seed_from_u64(0));
+    let validation_len = (positions.len() as f64 * VALIDATION_SPLIT) as usize;
+    let (validation, train) = positions.split_at(validation_len);
+    println!(
+        "{} Training positions, {} Validation positions",
+        train.len(),
+        validation.len()
+    );
+
+    let fitted = fitted();
+    let mut params = evaluate::Params::default();
+    let mut weights = to_weights(&params, &fitted);
+    let mut m = vec![0.0; weights.len()];
+    let mut v = vec![0.0; weights.len()];
+
+    let mut best_loss = loss(validation, &params);
+    let mut best_params = params;
+    println!(
+        "Initial Train: {} Validation: {}",
+        loss(train, &params),
+        best_loss
+    );
+
+    for epoch in 1..=EPOCHS {
+        let gradient = gradient(train, &params, &fitted);
+        for i in 0..weights.len() {
+            m[i] = BETA1 * m[i] + (1.0 - BETA1) * gradient[i];
+            v[i] = BETA2 * v[i] + (1.0 - BETA2) * gradient[i] * gradient[i];
+
+            let m_hat = m[i] / (1.0 - BETA1.powi(epoch as i32));
+            let v_hat = v[i] / (1.0 - BETA2.powi(epoch as i32));
+            weights[i] -= LEARNING_RATE * m_hat / (v_hat.sqrt() + EPSILON);
+        }
+
+        from_weights(&mut params, &fitted, &mut weights);
+
+        if epoch % REPORT_INTERVAL == 0 {
+            let validation_loss = loss(validation, &params);
+            println!(
+                "Epoch {epoch} Train: {} Validation: {validation_loss}",
+                loss(train, &params),
+            );
+
+            if validation_loss < best_loss {
+                best_loss = validation_loss;
+                best_params = params;
+            }
+        }
+    }
+
+    println!("Best Validation: {best_loss}");
+    print_params(&best_params);
+}
+
+/// Whether each `Eval` is fitted, in the same order as `evaluate::Params::evals`. Samples are
+/// taken at the start of a turn, before any player is eliminated or territory conquered in it, so
+/// `player_eliminated`, `territory_conquered` and `leader_elimination` are zero in every position.
+/// They get no gradient, so they are left out of the weights and keep their defaults.
+fn fitted() -> Vec<bool> {
+    let mut params = evaluate::Params::default();
+    for eval in params.evals_mut() {
+        *eval = Eval(1.0, 1.0);
+    }
+
+    params.player_eliminated = Eval(0.0, 0.0);
+    params.territory_conquered = Eval(0.0, 0.0);
+    params.leader_elimination = Eval(0.0, 0.0);
+    params.evals().map(|x| x != Eval(0.0, 0.0)).collect()
+}
+
+/// Every fitted `Eval` as two weights, followed by `resolve_k`
+fn to_weights(params: &evaluate::Params, fitted: &[bool]) -> Vec<f64> {
+    params
+        .evals()
+        .zip(fitted)
+        .filter(|(_, &fitted)| fitted)
+        .flat_map(|(x, _)| [x.0, x.1])
+        .chain([params.resolve_k])
+        .collect()
+}
+
+fn from_weights(params: &mut evaluate::Params, fitted: &[bool], weights: &mut [f64]) {
+    let resolve_k = weights.last_mut().unwrap();
+    *resolve_k = resolve_k.max(0.0);
+    params.resolve_k = *resolve_k;
+
+    let evals = params
+        .evals_mut()
+        .zip(fitted)
+        .filter(|(_, &fitted)| fitted)
+        .map(|(x, _)| x);
+    for (eval, weights) in evals.zip(weights.chunks_exact(2)) {
+        eval.0 = weights[0];
+        eval.1 = weights[1];
+    }
+}
+
+fn phase(params: &evaluate::Params, position: &Position) -> f64 {
+    (-params.resolve_k * position.card_sets_redeemed as f64).exp()
+}
+
+fn predict(params: &evaluate::Params, position: &Position, phase: f64) -> f64 {
+    let score = position
+        .values
+        .iter()
+        .zip(params.evals())
+        .map(|(&value, eval)| value * (eval.0 * phase + eval.1 * (1.0 - phase)))
+        .sum::<f64>();
+
+    1.0 / (1.0 + (-score).exp())
+}
+
+fn loss(positions: &[Position], params: &evaluate::Params) -> f64 {
+    let total = positions
+        .par_iter()
+        .map(|position| {
+            let prediction =
+                predict(params, position, phase(params, position)).clamp(1e-12, 1.0 - 1e-12);
+            -(position.outcome * prediction.ln()
+                + (1.0 - position.outcome) * (1.0 - prediction).ln())
+        })
+        .sum::<f64>();
+
+    total / positions.len() as f64
+}
+
+/// The gradient of the log loss with respect to the weights
+fn gradient(positions: &[Position], params: &evaluate::Params, fitted: &[bool]) -> Vec<f64> {
+    let num_weights = to_weights(params, fitted).len();
+    let total = positions
+        .par_iter()
+        .fold(
+            || vec![0.0; num_weights],
+            |mut gradient, position| {
+                let phase = phase(params, position);
+                let error = predict(params, position, phase) - position.outcome;
+
+                let mut phase_gradient = 0.0;
+                let mut i = 0;
+                for ((&value, eval), &fitted) in
+                    position.values.iter().zip(params.evals()).zip(fitted)
+                {
+                    phase_gradient += value * (eval.0 - eval.1);
+                    if fitted {
+                        gradient[2 * i] += error * value * phase;
+                        gradient[2 * i + 1] += error * value * (1.0 - phase);
+                        i += 1;
+                    }
+                }
+
+                gradient[num_weights - 1] -=
+                    error * phase_gradient * phase * position.card_sets_redeemed as f64;
+                gradient
+            },
+        )
+        .reduce(
+            || vec![0.0; num_weights],
+            |mut x, y| {
+                x.iter_mut().zip(y).for_each(|(x, y)| *x += y);
+                x
+            },
+        );
+
+    total
+        .into_iter()
+        .map(|x| x / positions.len() as f64)
+        .collect()
+}
+
+/// Prints the parameters in the same layout as `evaluate::Params::default`, marking the evals that
+/// are not fitted
+fn print_params(params: &evaluate::Params) {
+    println!("evaluate::Params {{");
+    println!("    territory_occupied: {:?},", params.territory_occupied);
+    println!("    weak_territory: {:?},", params.weak_territory);
+    println!("    isolated_territory: {:?},", params.isolated_territory);
+    println!(
+        "    player_eliminated: {:?}, // Not fitted",
+        params.player_eliminated
+    );
+    println!(
+        "    territory_conquered: {:?}, // Not fitted",
+        params.territory_conquered
+    );
+    println!("    troop_count: {:?},", params.troop_count);
+    println!("    resolve_k: {:?},", params.resolve_k);
+    println!("    bias: {:?},", params.bias);
+    println!("    continent_by_player: EnumMap::from_array([");
+    for players in params.continent_by_player.values() {
+        println!("        EnumMap::from_array([");
+        for eval in players.values() {
+            println!("            {eval:?},");
+        }
+        println!("        ]),");
+    }
+    println!("    ]),");
+    println!("    opponent_by_rank: [");
+    for opponent in &params.opponent_by_rank {
+        println!("        OpponentParams {{");
+        println!("            territories: {:?},", opponent.territories);
+        println!("            troops: {:?},", opponent.troops);
+        println!("            income: {:?},", opponent.income);
+        println!("            cards: {:?},", opponent.cards);
+        println!("        }},");
+    }
+    println!("    ],");
+    println!(
+        "    leader_elimination: {:?}, // Not fitted",
+        params.leader_elimination
+    );
+    println!("}}");
+}
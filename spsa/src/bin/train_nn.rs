@@ -0,0 +1,301 @@
+use std::io::{BufRead, BufReader};
+
+use attack_game::{
+    game::AttackGame,
+    nn::{self, FloatWeights, Network, HIDDEN, INPUTS, MAX_ACTIVATION, OUTPUTS},
+    strategy::puct,
+};
+use datagen::Sample;
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use risk_shared::map::TerritoryId;
+
+const DATA_FILE: &str = "data.jsonl";
+const OUTPUT_FILE: &str = "nn.bin";
+const VALIDATION_SPLIT: f64 = 0.1;
+const EPOCHS: usize = 200;
+const BATCH_SIZE: usize = 256;
+
+/// The weight of the strength loss relative to the value loss
+const POLICY_WEIGHT: f32 = 0.1;
+/// The log strength targets are clamped to this, since interior territories are infinitely strong
+const MAX_LOG_STRENGTH: f32 = 4.0;
+
+const LEARNING_RATE: f32 = 0.001;
+const BETA1: f32 = 0.9;
+const BETA2: f32 = 0.999;
+const EPSILON: f32 = 1e-8;
+
+struct Position {
+    game: AttackGame,
+    inputs: [f32; INPUTS],
+    outcome: f32,
+    /// The log strength of each territory, or `None` if we have no troops next to it
+    strengths: Vec<Option<f32>>,
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let path = args.next().unwrap_or_else(|| DATA_FILE.to_owned());
+    let output = args.next().unwrap_or_else(|| OUTPUT_FILE.to_owned());
+
+    let strength_params = puct::Params::default();
+    let file = std::fs::File::open(&path).unwrap();
+    let mut positions = BufReader::new(file)
+        .lines()
+        .map(|line| {
+            let sample = serde_json::from_str::<Sample>(&line.unwrap()).unwrap();
+            let game = sample.game();
+            let strengths = TerritoryId::ALL
+                .into_iter()
+                .map(|t| {
+                    let strength = puct::territory_strength(&game, t, &strength_params);
+                    (strength > 0.0)
+                        .then(|| (strength.ln() as f32).clamp(-MAX_LOG_STRENGTH, MAX_LOG_STRENGTH))
+                })
+                .collect();
+
+            Position {
+                inputs: nn::inputs(&game),
+                game,
+                outcome: sample.outcome as f32,
+                strengths,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let mut rng = rand_xoshiro::Xoshiro256StarStar::seed_from_u64(0);
+    positions.shuffle(&mut rng);
+    let validation_len = (positions.len() as f64 * VALIDATION_SPLIT) as usize;
+    let (validation, train) = positions.split_at_mut(validation_len);
+    println!(
+        "{} Training positions, {} Validation positions",
+        train.len(),
+        validation.len()
+    );
+
+    let mut weights = FloatWeights {
+        w1: initial_weights(INPUTS, INPUTS * HIDDEN, &mut rng),
+        b1: vec![0.0; HIDDEN],
+        w2: initial_weights(HIDDEN, HIDDEN * OUTPUTS, &mut rng),
+        b2: vec![0.0; OUTPUTS],
+    };
+
+    let mut adam = Adam::new(&weights);
+    let mut best_loss = f32::INFINITY;
+    for epoch in 1..=EPOCHS {
+        train.shuffle(&mut rng);
+        for batch in train.chunks(BATCH_SIZE) {
+            let gradient = gradient(batch, &weights);
+            adam.step(&mut weights, &gradient);
+        }
+
+        let network = Network::quantize(&weights);
+        let (value_loss, policy_loss) = quantized_loss(validation, &network);
+        let validation_loss = value_loss + POLICY_WEIGHT * policy_loss;
+        println!(
+            "Epoch {epoch} Train: {} Validation: {validation_loss} (Value: {value_loss} Strength: {policy_loss})",
+            loss(train, &weights),
+        );
+
+        if validation_loss < best_loss {
+            best_loss = validation_loss;
+            std::fs::write(&output, network.to_bytes()).unwrap();
+        }
+    }
+
+    println!("Best Validation: {best_loss}");
+}
+
+fn initial_weights(fan_in: usize, len: usize, rng: &mut impl Rng) -> Vec<f32> {
+    let limit = (6.0 / fan_in as f32).sqrt();
+    (0..len).map(|_| rng.gen_range(-limit..limit)).collect()
+}
+
+struct Forward {
+    hidden: [f32; HIDDEN],
+    outputs: [f32; OUTPUTS],
+}
+
+fn forward(weights: &FloatWeights, inputs: &[f32; INPUTS]) -> Forward {
+    let mut hidden = [0.0; HIDDEN];
+    for (i, row) in weights.w1.chunks_exact(INPUTS).enumerate() {
+        let x = row.iter().zip(inputs).map(|(w, x)| w * x).sum::<f32>() + weights.b1[i];
+        hidden[i] = x.clamp(0.0, MAX_ACTIVATION);
+    }
+
+    let mut outputs = [0.0; OUTPUTS];
+    for (i, row) in weights.w2.chunks_exact(HIDDEN).enumerate() {
+        outputs[i] = row.iter().zip(&hidden).map(|(w, x)| w * x).sum::<f32>() + weights.b2[i];
+    }
+
+    Forward { hidden, outputs }
+}
+
+fn sigmoid(x: f32) -> f32 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+fn value_loss(prediction: f32, outcome: f32) -> f32 {
+    let prediction = prediction.clamp(1e-6, 1.0 - 1e-6);
+    -(outcome * prediction.ln() + (1.0 - outcome) * (1.0 - prediction).ln())
+}
+
+/// The mean squared error of the log strengths we have targets for
+fn policy_loss(log_strength: impl Fn(usize) -> f32, strengths: &[Option<f32>]) -> f32 {
+    let (total, count) = strengths
+        .iter()
+        .enumerate()
+        .filter_map(|(i, x)| x.map(|x| (log_strength(i) - x).powi(2)))
+        .fold((0.0, 0), |(total, count), x| (total + x, count + 1));
+
+    total / count.max(1) as f32
+}
+
+fn loss(positions: &[Position], weights: &FloatWeights) -> f32 {
+    let total = positions
+        .par_iter()
+        .map(|position| {
+            let forward = forward(weights, &position.inputs);
+            value_loss(sigmoid(forward.outputs[0]), position.outcome)
+                + POLICY_WEIGHT * policy_loss(|i| forward.outputs[1 + i], &position.strengths)
+        })
+        .sum::<f32>();
+
+    total / positions.len() as f32
+}
+
+/// The value and strength losses of the quantized network used for inference
+fn quantized_loss(positions: &[Position], network: &Network) -> (f32, f32) {
+    let (value, policy) = positions
+        .par_iter()
+        .map(|position| {
+            let output = network.forward(&position.game);
+            let log_strength = |i| (output.strength(TerritoryId::ALL[i]) as f32).ln();
+            (
+                value_loss(output.value() as f32, position.outcome),
+                policy_loss(log_strength, &position.strengths),
+            )
+        })
+        .reduce(|| (0.0, 0.0), |x, y| (x.0 + y.0, x.1 + y.1));
+
+    (
+        value / positions.len() as f32,
+        policy / positions.len() as f32,
+    )
+}
+
+fn gradient(positions: &[Position], weights: &FloatWeights) -> FloatWeights {
+    let zero = || FloatWeights {
+        w1: vec![0.0; INPUTS * HIDDEN],
+        b1: vec![0.0; HIDDEN],
+        w2: vec![0.0; HIDDEN * OUTPUTS],
+        b2: vec![0.0; OUTPUTS],
+    };
+
+    let total = positions
+        .par_iter()
+        .fold(zero, |mut gradient, position| {
+            let forward = forward(weights, &position.inputs);
+
+            let mut output_gradient = [0.0; OUTPUTS];
+            output_gradient[0] = sigmoid(forward.outputs[0]) - position.outcome;
+
+            let count = position.strengths.iter().flatten().count().max(1) as f32;
+            for (i, strength) in position.strengths.iter().enumerate() {
+                if let Some(strength) = strength {
+                    output_gradient[1 + i] =
+                        POLICY_WEIGHT * 2.0 * (forward.outputs[1 + i] - strength) / count;
+                }
+            }
+
+            let mut hidden_gradient = [0.0; HIDDEN];
+            for (i, &output_gradient) in output_gradient.iter().enumerate() {
+                gradient.b2[i] += output_gradient;
+                for (j, hidden_gradient) in hidden_gradient.iter_mut().enumerate() {
+                    gradient.w2[i * HIDDEN + j] += output_gradient * forward.hidden[j];
+                    *hidden_gradient += output_gradient * weights.w2[i * HIDDEN + j];
+                }
+            }
+
+            for (j, &hidden_gradient) in hidden_gradient.iter().enumerate() {
+                // The clipped activation has no gradient outside of its range
+                if forward.hidden[j] <= 0.0 || forward.hidden[j] >= MAX_ACTIVATION {
+                    continue;
+                }
+
+                gradient.b1[j] += hidden_gradient;
+                for (k, &input) in position.inputs.iter().enumerate() {
+                    gradient.w1[j * INPUTS + k] += hidden_gradient * input;
+                }
+            }
+
+            gradient
+        })
+        .reduce(zero, |mut x, y| {
+            for (x, y) in layers_mut(&mut x).zip(layers(&y)) {
+                x.iter_mut().zip(y).for_each(|(x, y)| *x += y);
+            }
+
+            x
+        });
+
+    let mut gradient = total;
+    for layer in layers_mut(&mut gradient) {
+        layer.iter_mut().for_each(|x| *x /= positions.len() as f32);
+    }
+
+    gradient
+}
+
+fn layers(weights: &FloatWeights) -> impl Iterator<Item = &Vec<f32>> {
+    [&weights.w1, &weights.b1, &weights.w2, &weights.b2].into_iter()
+}
+
+fn layers_mut(weights: &mut FloatWeights) -> impl Iterator<Item = &mut Vec<f32>> {
+    [
+        &mut weights.w1,
+        &mut weights.b1,
+        &mut weights.w2,
+        &mut weights.b2,
+    ]
+    .into_iter()
+}
+
+struct Adam {
+    m: Vec<Vec<f32>>,
+    v: Vec<Vec<f32>>,
+    steps: i32,
+}
+
+impl Adam {
+    fn new(weights: &FloatWeights) -> Self {
+        let zeros = layers(weights)
+            .map(|x| vec![0.0; x.len()])
+            .collect::<Vec<_>>();
+        Self {
+            m: zeros.clone(),
+            v: zeros,
+            steps: 0,
+        }
+    }
+
+    fn step(&mut self, weights: &mut FloatWeights, gradient: &FloatWeights) {
+        self.steps += 1;
+        let m_correction = 1.0 - BETA1.powi(self.steps);
+        let v_correction = 1.0 - BETA2.powi(self.steps);
+
+        for (((weights, gradient), m), v) in layers_mut(weights)
+            .zip(layers(gradient))
+            .zip(&mut self.m)
+            .zip(&mut self.v)
+        {
+            for i in 0..weights.len() {
+                m[i] = BETA1 * m[i] + (1.0 - BETA1) * gradient[i];
+                v[i] = BETA2 * v[i] + (1.0 - BETA2) * gradient[i] * gradient[i];
+                weights[i] -= LEARNING_RATE * (m[i] / m_correction)
+                    / ((v[i] / v_correction).sqrt() + EPSILON);
+            }
+        }
+    }
+}
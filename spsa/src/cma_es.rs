@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+
+use crate::{
+    spsa::{HyperParams, SpsaParam},
+    tuner::{self, Games, Tuner},
+};
+
+/// Separable CMA-ES, which only adapts the variance of each parameter rather than the full
+/// covariance matrix. This scales to many parameters with few samples. Each candidate is played
+/// against the current mean, and parameters are measured in units of their SPSA perturbation `c`.
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct CmaEs {
+    keys: Vec<String>,
+    params: HashMap<String, SpsaParam>,
+    hyper_params: HyperParams,
+    mean: Vec<f64>,
+    sigma: f64,
+    variance: Vec<f64>,
+    path_sigma: Vec<f64>,
+    path_c: Vec<f64>,
+    generation: u64,
+    curr_iteration: u64,
+}
+
+/// The strategy parameters recommended for the number of dimensions
+struct Constants {
+    population: usize,
+    weights: Vec<f64>,
+    mu_eff: f64,
+    c_sigma: f64,
+    d_sigma: f64,
+    c_c: f64,
+    c_1: f64,
+    c_mu: f64,
+    expected_norm: f64,
+}
+
+impl Constants {
+    fn new(dimensions: usize) -> Self {
+        let n = dimensions as f64;
+        let population = 4 + (3.0 * n.ln()).floor() as usize;
+        let parents = population / 2;
+
+        let weights = (1..=parents)
+            .map(|i| (parents as f64 + 0.5).ln() - (i as f64).ln())
+            .collect::<Vec<_>>();
+        let total = weights.iter().sum::<f64>();
+        let weights = weights.into_iter().map(|x| x / total).collect::<Vec<_>>();
+        let mu_eff = 1.0 / weights.iter().map(|x| x * x).sum::<f64>();
+
+        let c_sigma = (mu_eff + 2.0) / (n + mu_eff + 5.0);
+        let d_sigma =
+            1.0 + 2.0 * f64::max(0.0, ((mu_eff - 1.0) / (n + 1.0)).sqrt() - 1.0) + c_sigma;
+        let c_c = (4.0 + mu_eff / n) / (n + 4.0 + 2.0 * mu_eff / n);
+
+        // The learning rates of the full covariance matrix, scaled up for the diagonal
+        let c_1 = 2.0 / ((n + 1.3).powi(2) + mu_eff) * (n + 2.0) / 3.0;
+        let c_mu = f64::min(
+            1.0 - c_1,
+            2.0 * (mu_eff - 2.0 + 1.0 / mu_eff) / ((n + 2.0).powi(2) + mu_eff) * (n + 2.0) / 3.0,
+        );
+
+        Self {
+            population,
+            weights,
+            mu_eff,
+            c_sigma,
+            d_sigma,
+            c_c,
+            c_1,
+            c_mu,
+            expected_norm: n.sqrt() * (1.0 - 1.0 / (4.0 * n) + 1.0 / (21.0 * n * n)),
+        }
+    }
+}
+
+impl CmaEs {
+    fn scale(&self, i: usize) -> f64 {
+        let c = self.params[&self.keys[i]].c;
+        if c > 0.0 {
+            c
+        } else {
+            1.0
+        }
+    }
+
+    fn clamp(&self, x: &mut [f64]) {
+        for (i, x) in x.iter_mut().enumerate() {
+            let param = &self.params[&self.keys[i]];
+            let scale = self.scale(i);
+            *x = x.clamp(param.min / scale, param.max / scale);
+        }
+    }
+
+    fn to_values(&self, x: &[f64]) -> HashMap<String, f64> {
+        let x = x
+            .iter()
+            .enumerate()
+            .map(|(i, x)| x * self.scale(i))
+            .collect::<Vec<_>>();
+        tuner::to_values(&self.keys, &x)
+    }
+}
+
+impl Tuner for CmaEs {
+    fn new(params: HashMap<String, SpsaParam>, hyper_params: HyperParams) -> Self {
+        let keys = tuner::sorted_keys(&params);
+        let n = keys.len();
+        let mut cma_es = Self {
+            keys,
+            params,
+            hyper_params,
+            mean: Vec::new(),
+            sigma: 1.0,
+            variance: vec![1.0; n],
+            path_sigma: vec![0.0; n],
+            path_c: vec![0.0; n],
+            generation: 0,
+            curr_iteration: 0,
+        };
+
+        cma_es.mean = (0..n)
+            .map(|i| cma_es.params[&cma_es.keys[i]].curr_value / cma_es.scale(i))
+            .collect();
+        cma_es
+    }
+
+    fn values(&self) -> HashMap<String, f64> {
+        self.to_values(&self.mean)
+    }
+
+    fn curr_iteration(&self) -> u64 {
+        self.curr_iteration
+    }
+
+    fn num_iterations(&self) -> u64 {
+        self.hyper_params.num_iterations()
+    }
+
+    fn step<G, R>(&mut self, games: &G, rng: &mut R)
+    where
+        G: Games,
+        R: rand::Rng,
+    {
+        let n = self.keys.len();
+        let constants = Constants::new(n);
+
+        // The steps from the mean, before they are scaled by sigma
+        let mut steps = Vec::with_capacity(constants.population);
+        let mut candidates = Vec::with_capacity(constants.population);
+        for _ in 0..constants.population {
+            let mut x = (0..n)
+                .map(|i| {
+                    self.mean[i] + self.sigma * self.variance[i].sqrt() * tuner::gen_normal(rng)
+                })
+                .collect::<Vec<_>>();
+            self.clamp(&mut x);
+
+            steps.push(
+                x.iter()
+                    .zip(&self.mean)
+                    .map(|(x, m)| (x - m) / self.sigma)
+                    .collect::<Vec<_>>(),
+            );
+            candidates.push(self.to_values(&x));
+        }
+
+        let scores = games.play_games(&candidates, &self.values(), self.hyper_params.games_per());
+
+        let mut order = (0..constants.population).collect::<Vec<_>>();
+        order.sort_by(|&x, &y| scores[y].total_cmp(&scores[x]));
+
+        let mut step = vec![0.0; n];
+        for (&weight, &k) in constants.weights.iter().zip(&order) {
+            for (total, x) in step.iter_mut().zip(&steps[k]) {
+                *total += weight * x;
+            }
+        }
+
+        for (mean, x) in self.mean.iter_mut().zip(&step) {
+            *mean += self.sigma * x;
+        }
+        let mut mean = std::mem::take(&mut self.mean);
+        self.clamp(&mut mean);
+        self.mean = mean;
+
+        let c_sigma = constants.c_sigma;
+        let sigma_rate = (c_sigma * (2.0 - c_sigma) * constants.mu_eff).sqrt();
+        for ((path, x), variance) in self.path_sigma.iter_mut().zip(&step).zip(&self.variance) {
+            *path = (1.0 - c_sigma) * *path + sigma_rate * x / variance.sqrt();
+        }
+
+        let norm = self.path_sigma.iter().map(|x| x * x).sum::<f64>().sqrt();
+        let decay = 1.0 - (1.0 - c_sigma).powi(2 * (self.generation as i32 + 1));
+        let stalled =
+            norm / decay.sqrt() >= (1.4 + 2.0 / (n as f64 + 1.0)) * constants.expected_norm;
+        let h_sigma = if stalled { 0.0 } else { 1.0 };
+
+        let c_c = constants.c_c;
+        let c_rate = (c_c * (2.0 - c_c) * constants.mu_eff).sqrt();
+        for i in 0..n {
+            self.path_c[i] = (1.0 - c_c) * self.path_c[i] + h_sigma * c_rate * step[i];
+
+            let rank_mu = constants
+                .weights
+                .iter()
+                .zip(&order)
+                .map(|(weight, &k)| weight * steps[k][i].powi(2))
+                .sum::<f64>();
+
+            self.variance[i] = (1.0 - constants.c_1 - constants.c_mu) * self.variance[i]
+                + constants.c_1
+                    * (self.path_c[i].powi(2)
+                        + (1.0 - h_sigma) * c_c * (2.0 - c_c) * self.variance[i])
+                + constants.c_mu * rank_mu;
+        }
+
+        self.sigma *=
+            ((c_sigma / constants.d_sigma) * (norm / constants.expected_norm - 1.0)).exp();
+        self.generation += 1;
+        self.curr_iteration += constants.population as u64 * self.hyper_params.games_per();
+    }
+}
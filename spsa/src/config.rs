@@ -0,0 +1,291 @@
+use std::collections::{BTreeMap, HashMap};
+
+use risk_bots::registry::{self, BotConfig};
+use risk_engine::{book::Book, game_engine::Statistic};
+use risk_shared::player::PlayerBot;
+
+use crate::{
+    bayes::Bayes,
+    cma_es::CmaEs,
+    spsa::{HyperParams, Spsa, SpsaParam},
+    tuner::{self, Games, Tuner},
+    CreateFromParams,
+};
+
+/// A tuning run read from a JSON file, e.g.
+/// ```json
+/// {
+///     "bot": { "bot": "puct", "params": { "simulate_opponents": 1 } },
+///     "tuner": "cma_es",
+///     "tune": {
+///         "c_puct": {},
+///         "draft_iterations": { "min": 500, "max": 5000, "c_end": 100, "r_end": 0.01 }
+///     },
+///     "save_file": "tune.spsa",
+///     "history_file": "tune.csv"
+/// }
+/// ```
+#[derive(Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpsaConfig {
+    /// The bot being tuned, with the parameters that are fixed
+    pub bot: BotConfig,
+    #[serde(default)]
+    pub tuner: TunerKind,
+    /// Every parameter of the bot with bounds that is not fixed is tuned if this is `None`
+    #[serde(default)]
+    pub tune: Option<BTreeMap<String, TunedParam>>,
+    /// The bots in the other three seats
+    #[serde(default = "registry::default_opponents")]
+    pub opponents: Vec<BotConfig>,
+    /// The openings that games start from, which are played from scratch without a book
+    #[serde(default)]
+    pub book: Option<String>,
+    /// How each game between a candidate and the reference is scored
+    #[serde(default)]
+    pub statistic: Statistic,
+    #[serde(default = "default_iterations")]
+    pub iterations: u64,
+    #[serde(default = "default_games_per")]
+    pub games_per: u64,
+    /// The number of games played at once, which defaults to the number of threads available
+    #[serde(default)]
+    pub threads: Option<usize>,
+    pub save_file: String,
+    pub history_file: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TunerKind {
+    #[default]
+    Spsa,
+    CmaEs,
+    Bayes,
+}
+
+/// The settings that a tuner is saved with, which must be the same to resume it
+#[derive(Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+struct SavedRun {
+    tuner: TunerKind,
+    /// The bot with only its fixed parameters
+    bot: BotConfig,
+    opponents: Vec<BotConfig>,
+    book: Option<String>,
+    statistic: Statistic,
+    iterations: u64,
+    games_per: u64,
+}
+
+/// Overrides the default value and bounds of a tuned parameter
+#[derive(Clone, Copy, PartialEq, Default, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TunedParam {
+    pub value: Option<f64>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub c_end: Option<f64>,
+    pub r_end: Option<f64>,
+}
+
+fn default_iterations() -> u64 {
+    10000
+}
+
+fn default_games_per() -> u64 {
+    4
+}
+
+impl SpsaConfig {
+    /// Tunes every parameter of the bot
+    pub fn new(bot: BotConfig, save_file: &str, history_file: &str) -> Self {
+        Self {
+            bot,
+            tuner: TunerKind::Spsa,
+            tune: None,
+            opponents: registry::default_opponents(),
+            book: None,
+            statistic: Statistic::Winner,
+            iterations: default_iterations(),
+            games_per: default_games_per(),
+            threads: None,
+            save_file: save_file.to_owned(),
+            history_file: history_file.to_owned(),
+        }
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let contents =
+            std::fs::read_to_string(path).map_err(|e| format!("Cannot read {path}: {e}"))?;
+        let config = serde_json::from_str::<Self>(&contents)
+            .map_err(|e| format!("Cannot parse {path}: {e}"))?;
+
+        if config.opponents.is_empty() {
+            return Err(format!("{path} has no opponents"));
+        }
+
+        let values = config
+            .spsa_params()?
+            .into_iter()
+            .map(|(key, param)| (key, param.curr_value))
+            .collect();
+        config.try_create_from_params(&values)?;
+        for bot in &config.opponents {
+            bot.validate()?;
+        }
+
+        Ok(config)
+    }
+
+    pub fn book(&self) -> Result<Option<Book>, String> {
+        self.book.as_deref().map(Book::load).transpose()
+    }
+
+    pub fn hyper_params(&self) -> HyperParams {
+        HyperParams::new(self.iterations, self.games_per)
+    }
+
+    /// The tuned parameters, with their values and bounds defaulting to the bot's
+    pub fn spsa_params(&self) -> Result<HashMap<String, SpsaParam>, String> {
+        let hyper_params = self.hyper_params();
+        let defaults = registry::params(&self.bot.bot)?;
+
+        let tune = match &self.tune {
+            Some(tune) => tune.clone(),
+            None => defaults
+                .iter()
+                .filter(|x| x.bounds.is_some() && !self.bot.params.contains_key(&x.name))
+                .map(|x| (x.name.clone(), TunedParam::default()))
+                .collect(),
+        };
+
+        tune.into_iter()
+            .map(|(key, x)| {
+                if self.bot.params.contains_key(&key) {
+                    return Err(format!("{key} is both fixed and tuned"));
+                }
+
+                let default = defaults
+                    .iter()
+                    .find(|x| x.name == key)
+                    .ok_or_else(|| format!("Unknown parameter {key}"))?;
+
+                let bounds = default.bounds;
+                let missing = || format!("{key} has no default bounds, so they must be given");
+                let param = hyper_params.make_params(
+                    x.value.unwrap_or(default.value),
+                    x.min.or(bounds.map(|x| x.min)).ok_or_else(missing)?,
+                    x.max.or(bounds.map(|x| x.max)).ok_or_else(missing)?,
+                    x.c_end.or(bounds.map(|x| x.c_end)).ok_or_else(missing)?,
+                    x.r_end.or(bounds.map(|x| x.r_end)).ok_or_else(missing)?,
+                );
+
+                Ok((key, param))
+            })
+            .collect()
+    }
+
+    /// Tunes the bot with the chosen tuner, resuming from the save file if there is one
+    pub fn run(&self, games: &impl Games) -> Result<HashMap<String, f64>, String> {
+        let params = self.spsa_params()?;
+        println!("Params: {params:#?}");
+
+        match self.tuner {
+            TunerKind::Spsa => self.tune::<Spsa>(params, games),
+            TunerKind::CmaEs => self.tune::<CmaEs>(params, games),
+            TunerKind::Bayes => self.tune::<Bayes>(params, games),
+        }
+    }
+
+    /// The bot with the tuned parameters set to `params`
+    pub fn bot_with(&self, params: &HashMap<String, f64>) -> BotConfig {
+        let mut bot = self.bot.clone();
+        bot.params
+            .extend(params.iter().map(|(k, v)| (k.clone(), *v)));
+        bot
+    }
+
+    fn tune<U: Tuner>(
+        &self,
+        params: HashMap<String, SpsaParam>,
+        games: &impl Games,
+    ) -> Result<HashMap<String, f64>, String> {
+        let save_file = &self.save_file;
+        let run = SavedRun {
+            tuner: self.tuner,
+            bot: self.bot.clone(),
+            opponents: self.opponents.clone(),
+            book: self.book.clone(),
+            statistic: self.statistic,
+            iterations: self.iterations,
+            games_per: self.games_per,
+        };
+
+        let mut tuner = match tuner::load::<SavedRun>(save_file) {
+            Some(saved) => {
+                let mismatch = |what| Err(format!("Cannot resume from {save_file} with {what}"));
+                if saved.run.tuner != run.tuner {
+                    return mismatch("a different tuner");
+                }
+                if saved.run.bot != run.bot {
+                    return mismatch("a different bot or fixed parameters");
+                }
+                if saved.run.opponents != run.opponents {
+                    return mismatch("different opponents");
+                }
+                if saved.run.book != run.book {
+                    return mismatch("a different book");
+                }
+                if saved.run.statistic != run.statistic {
+                    return mismatch("a different statistic");
+                }
+                if (saved.run.iterations, saved.run.games_per) != (run.iterations, run.games_per) {
+                    return mismatch("a different number of iterations or games");
+                }
+
+                let tuner = serde_json::from_value::<U>(saved.tuner)
+                    .unwrap_or_else(|e| panic!("Cannot resume from {save_file}: {e}"));
+
+                let mut saved_keys = tuner.values().into_keys().collect::<Vec<_>>();
+                let mut keys = params.keys().cloned().collect::<Vec<_>>();
+                saved_keys.sort();
+                keys.sort();
+                if saved_keys != keys {
+                    return mismatch("different parameters");
+                }
+
+                println!(
+                    "Resuming from {save_file} at iteration {}",
+                    tuner.curr_iteration()
+                );
+                tuner
+            }
+            None => U::new(params, self.hyper_params()),
+        };
+
+        Ok(tuner::tune(
+            &run,
+            &mut tuner,
+            games,
+            rand::thread_rng(),
+            save_file,
+            &self.history_file,
+        ))
+    }
+
+    fn try_create_from_params(
+        &self,
+        params: &HashMap<String, f64>,
+    ) -> Result<Box<dyn PlayerBot>, String> {
+        self.bot_with(params).try_create()
+    }
+}
+
+impl CreateFromParams for SpsaConfig {
+    type Bot = Box<dyn PlayerBot>;
+
+    fn create_from_params(&self, params: &HashMap<String, f64>) -> Self::Bot {
+        self.try_create_from_params(params)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+}
@@ -0,0 +1,245 @@
+use std::{
+    collections::HashMap,
+    fs::OpenOptions,
+    io::{BufWriter, Write},
+};
+
+use enum_map::EnumMap;
+use rand::Rng;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use risk_bots::registry::{self, BotConfig};
+use risk_engine::{
+    book::Book,
+    game_engine::{GameEngine, Statistic},
+    player::PlayerConnection,
+};
+use risk_shared::player::{PlayerBot, PlayerId};
+
+use crate::{
+    spsa::{HyperParams, SpsaParam},
+    CreateFromParams,
+};
+
+/// A black-box tuner, which is saved as JSON after every step so that it can be resumed
+pub trait Tuner: serde::Serialize + serde::de::DeserializeOwned {
+    fn new(params: HashMap<String, SpsaParam>, hyper_params: HyperParams) -> Self;
+
+    /// The current estimate of the best value of each parameter
+    fn values(&self) -> HashMap<String, f64>;
+
+    /// The number of games played so far
+    fn curr_iteration(&self) -> u64;
+
+    fn num_iterations(&self) -> u64;
+
+    /// Plays a batch of games and updates the estimate
+    fn step<G, R>(&mut self, games: &G, rng: &mut R)
+    where
+        G: Games,
+        R: rand::Rng;
+}
+
+/// The contents of the save file, with the settings of the run that must match to resume it
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct Saved<R, U> {
+    pub run: R,
+    pub tuner: U,
+}
+
+/// Loads the state saved by `tune`, or returns `None` if there is no save file. The tuner is left
+/// as JSON, since it can only be parsed once the run is known to match.
+pub fn load<R>(save_file: &str) -> Option<Saved<R, serde_json::Value>>
+where
+    R: serde::de::DeserializeOwned,
+{
+    match std::fs::read_to_string(save_file) {
+        Ok(contents) => Some(
+            serde_json::from_str(&contents)
+                .unwrap_or_else(|e| panic!("Cannot resume from {save_file}: {e}")),
+        ),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => panic!("Cannot read {save_file}: {e}"),
+    }
+}
+
+/// Plays the games needed by a tuner, which lets them be played somewhere other than this process
+pub trait Games {
+    /// The total score of each candidate against the reference over `games_per` games, from -1
+    /// to 1 for each game
+    fn play_games(
+        &self,
+        candidates: &[HashMap<String, f64>],
+        reference: &HashMap<String, f64>,
+        games_per: u64,
+    ) -> Vec<f64>;
+}
+
+/// Plays every game in parallel on the current thread pool, with the other seats filled from
+/// `opponents`. Each game starts from an opening drawn from the book if there is one, and is scored
+/// with `statistic`.
+pub struct LocalGames<'a, T> {
+    create: &'a T,
+    opponents: &'a [BotConfig],
+    book: Option<&'a Book>,
+    statistic: Statistic,
+}
+
+impl<'a, T> LocalGames<'a, T> {
+    pub fn new(
+        create: &'a T,
+        opponents: &'a [BotConfig],
+        book: Option<&'a Book>,
+        statistic: Statistic,
+    ) -> Self {
+        Self {
+            create,
+            opponents,
+            book,
+            statistic,
+        }
+    }
+}
+
+impl<T> Games for LocalGames<'_, T>
+where
+    T: CreateFromParams + Sync + 'static,
+{
+    fn play_games(
+        &self,
+        candidates: &[HashMap<String, f64>],
+        reference: &HashMap<String, f64>,
+        games_per: u64,
+    ) -> Vec<f64> {
+        let results = (0..candidates.len() as u64 * games_per)
+            .into_par_iter()
+            .map(|i| {
+                let candidate = &candidates[(i / games_per) as usize];
+                play_game(
+                    self.create,
+                    candidate,
+                    reference,
+                    self.opponents,
+                    self.book,
+                    self.statistic,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        results
+            .chunks(games_per as usize)
+            .map(|x| x.iter().sum::<f64>())
+            .collect()
+    }
+}
+
+/// Tunes until the last iteration. After every step, the state is saved to `save_file` along with
+/// `run`, and the values are appended to `history_file` as CSV.
+pub fn tune<S, U, G, R>(
+    run: &S,
+    tuner: &mut U,
+    games: &G,
+    mut rng: R,
+    save_file: &str,
+    history_file: &str,
+) -> HashMap<String, f64>
+where
+    S: serde::Serialize,
+    U: Tuner,
+    G: Games,
+    R: rand::Rng,
+{
+    let mut keys = tuner.values().into_keys().collect::<Vec<_>>();
+    keys.sort();
+
+    let history = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(history_file)
+        .unwrap();
+    let write_header = history.metadata().unwrap().len() == 0;
+    let mut history = BufWriter::new(history);
+    if write_header {
+        writeln!(history, "iteration,{}", keys.join(",")).unwrap();
+    }
+
+    let mut steps = 0;
+    while tuner.curr_iteration() < tuner.num_iterations() {
+        tuner.step(games, &mut rng);
+        steps += 1;
+
+        let values = tuner.values();
+        write!(history, "{}", tuner.curr_iteration()).unwrap();
+        for key in &keys {
+            write!(history, ",{}", values[key]).unwrap();
+        }
+        writeln!(history).unwrap();
+        history.flush().unwrap();
+
+        if steps % 5 == 0 {
+            println!("Iteration: {}", tuner.curr_iteration());
+            for key in &keys {
+                println!("{key}: {}", values[key]);
+            }
+        }
+
+        // Saved with every history row, so a resumed run continues the history exactly
+        let saved = Saved {
+            run,
+            tuner: &*tuner,
+        };
+        std::fs::write(save_file, serde_json::to_string(&saved).unwrap()).unwrap();
+    }
+
+    tuner.values()
+}
+
+fn play_game<T>(
+    create: &T,
+    params_a: &HashMap<String, f64>,
+    params_b: &HashMap<String, f64>,
+    opponents: &[BotConfig],
+    book: Option<&Book>,
+    statistic: Statistic,
+) -> f64
+where
+    T: CreateFromParams + 'static,
+{
+    let opponents = registry::choose_opponents(opponents);
+    let players = EnumMap::from_fn(|player| {
+        let bot = match player {
+            PlayerId::P0 => Box::new(create.create_from_params(params_a)) as Box<dyn PlayerBot>,
+            PlayerId::P1 => Box::new(create.create_from_params(params_b)) as Box<dyn PlayerBot>,
+            PlayerId::P2 => opponents[0].create(),
+            PlayerId::P3 => opponents[1].create(),
+            PlayerId::P4 => opponents[2].create(),
+        };
+        PlayerConnection::new(bot, player)
+    });
+
+    let mut rng = rand::thread_rng();
+    let mut game = match book {
+        Some(book) => GameEngine::with_opening(players, rng.gen(), book.choose(&mut rng).clone()),
+        None => GameEngine::new(players),
+    };
+
+    let result = game.start();
+    game.score(&result, statistic) as f64 - 1.0
+}
+
+/// The parameters in a fixed order, so that they can be treated as vectors
+pub(crate) fn sorted_keys(params: &HashMap<String, SpsaParam>) -> Vec<String> {
+    let mut keys = params.keys().cloned().collect::<Vec<_>>();
+    keys.sort();
+    keys
+}
+
+pub(crate) fn to_values(keys: &[String], x: &[f64]) -> HashMap<String, f64> {
+    keys.iter().cloned().zip(x.iter().copied()).collect()
+}
+
+/// A standard normal sample, from the Box-Muller transform
+pub(crate) fn gen_normal(rng: &mut impl rand::Rng) -> f64 {
+    let u = 1.0 - rng.gen::<f64>();
+    let v = rng.gen::<f64>();
+    (-2.0 * u.ln()).sqrt() * (std::f64::consts::TAU * v).cos()
+}
@@ -0,0 +1,241 @@
+//! Flattens parameter structs into named `f64` values, so that they can be tuned and configured
+//! by name. Implementations are generated with `tunable!`.
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Bounds {
+    pub min: f64,
+    pub max: f64,
+    /// The SPSA perturbation at the end of tuning
+    pub c_end: f64,
+    /// The SPSA learning rate at the end of tuning, relative to `c_end` squared
+    pub r_end: f64,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Param {
+    pub name: String,
+    pub value: f64,
+    /// The parameter is not tuned if this is `None`, but it can still be set by name
+    pub bounds: Option<Bounds>,
+}
+
+pub trait Tunable {
+    /// Appends every parameter, with `prefix` prepended to the names
+    fn params(&self, prefix: &str, params: &mut Vec<Param>);
+
+    /// Sets the parameter with the name, returning `false` if there is no such parameter
+    fn set_param(&mut self, name: &str, value: f64) -> bool;
+
+    fn all_params(&self) -> Vec<Param> {
+        let mut params = Vec::new();
+        self.params("", &mut params);
+        params
+    }
+
+    /// The default parameters, with the values set by name
+    fn from_values<'a>(values: impl IntoIterator<Item = (&'a str, f64)>) -> Result<Self, String>
+    where
+        Self: Default,
+    {
+        let mut params = Self::default();
+        for (name, value) in values {
+            if !params.set_param(name, value) {
+                return Err(format!("Unknown parameter {name}"));
+            }
+        }
+
+        Ok(params)
+    }
+}
+
+/// A field that is stored as a single `f64`. Flags are set by any non-zero value.
+pub trait Value {
+    fn get(&self) -> f64;
+
+    fn set(&mut self, value: f64);
+}
+
+impl Value for f64 {
+    fn get(&self) -> f64 {
+        *self
+    }
+
+    fn set(&mut self, value: f64) {
+        *self = value;
+    }
+}
+
+impl Value for u32 {
+    fn get(&self) -> f64 {
+        f64::from(*self)
+    }
+
+    fn set(&mut self, value: f64) {
+        *self = value.round().max(0.0) as u32;
+    }
+}
+
+impl Value for bool {
+    fn get(&self) -> f64 {
+        if *self {
+            1.0
+        } else {
+            0.0
+        }
+    }
+
+    fn set(&mut self, value: f64) {
+        *self = value != 0.0;
+    }
+}
+
+/// Enabled with the default value
+impl<T: Default> Value for Option<T> {
+    fn get(&self) -> f64 {
+        self.is_some().get()
+    }
+
+    fn set(&mut self, value: f64) {
+        *self = (value != 0.0).then(T::default);
+    }
+}
+
+/// Implements `Tunable` for a struct by listing its fields, each with one of the kinds:
+/// * `float(min, max, c_end, r_end)` for a tuned `Value`
+/// * `eval(min, max, c_end, r_end)` for a tuned pair, named with the suffixes `_0` and `_1`
+/// * `fixed` for a `Value` that is not tuned
+/// * `nested("prefix")` for a field that is `Tunable` itself
+/// * `with(module)` for a field handled by `module::params` and `module::set_param`
+///
+/// A field is named after itself unless it is renamed with `as "name"`. Generics and bounds are
+/// written in brackets, e.g. `impl[S: Trait] Tunable for Params<S> where [S::Params: Tunable]`.
+#[macro_export]
+macro_rules! tunable {
+    (
+        impl $([$($generics:tt)*])? Tunable for $ty:ty $(where [$($bounds:tt)*])? {
+            $($field:ident $(as $name:literal)?: $kind:ident $(($($args:tt)*))?),* $(,)?
+        }
+    ) => {
+        impl<$($($generics)*)?> $crate::Tunable for $ty where $($($bounds)*)? {
+            fn params(&self, prefix: &str, params: &mut Vec<$crate::Param>) {
+                $(
+                    $crate::__tunable_field!(
+                        params $kind($($($args)*)?),
+                        self.$field,
+                        $crate::__tunable_name!($field $(, $name)?),
+                        prefix,
+                        params
+                    );
+                )*
+            }
+
+            fn set_param(&mut self, name: &str, value: f64) -> bool {
+                $(
+                    if $crate::__tunable_field!(
+                        set $kind($($($args)*)?),
+                        self.$field,
+                        $crate::__tunable_name!($field $(, $name)?),
+                        name,
+                        value
+                    ) {
+                        return true;
+                    }
+                )*
+
+                false
+            }
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __tunable_name {
+    ($field:ident) => {
+        stringify!($field)
+    };
+    ($field:ident, $name:literal) => {
+        $name
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __tunable_field {
+    (params float($min:expr, $max:expr, $c_end:expr, $r_end:expr), $field:expr, $name:expr, $prefix:expr, $params:expr) => {
+        $params.push($crate::Param {
+            name: format!("{}{}", $prefix, $name),
+            value: $crate::Value::get(&$field),
+            bounds: Some($crate::Bounds {
+                min: $min,
+                max: $max,
+                c_end: $c_end,
+                r_end: $r_end,
+            }),
+        })
+    };
+    (set float($($args:tt)*), $field:expr, $name:expr, $key:expr, $value:expr) => {
+        $crate::__tunable_field!(set fixed(), $field, $name, $key, $value)
+    };
+
+    (params eval($min:expr, $max:expr, $c_end:expr, $r_end:expr), $field:expr, $name:expr, $prefix:expr, $params:expr) => {
+        for (suffix, value) in [("_0", $field.0), ("_1", $field.1)] {
+            $params.push($crate::Param {
+                name: format!("{}{}{}", $prefix, $name, suffix),
+                value,
+                bounds: Some($crate::Bounds {
+                    min: $min,
+                    max: $max,
+                    c_end: $c_end,
+                    r_end: $r_end,
+                }),
+            })
+        }
+    };
+    (set eval($($args:tt)*), $field:expr, $name:expr, $key:expr, $value:expr) => {
+        match $key.strip_prefix($name) {
+            Some("_0") => {
+                $field.0 = $value;
+                true
+            }
+            Some("_1") => {
+                $field.1 = $value;
+                true
+            }
+            _ => false,
+        }
+    };
+
+    (params fixed(), $field:expr, $name:expr, $prefix:expr, $params:expr) => {
+        $params.push($crate::Param {
+            name: format!("{}{}", $prefix, $name),
+            value: $crate::Value::get(&$field),
+            bounds: None,
+        })
+    };
+    (set fixed(), $field:expr, $name:expr, $key:expr, $value:expr) => {
+        if $key == $name {
+            $crate::Value::set(&mut $field, $value);
+            true
+        } else {
+            false
+        }
+    };
+
+    (params nested($prefix:literal), $field:expr, $name:expr, $outer:expr, $params:expr) => {
+        $crate::Tunable::params(&$field, &format!("{}{}", $outer, $prefix), $params)
+    };
+    (set nested($prefix:literal), $field:expr, $name:expr, $key:expr, $value:expr) => {
+        match $key.strip_prefix($prefix) {
+            Some(key) => $crate::Tunable::set_param(&mut $field, key, $value),
+            None => false,
+        }
+    };
+
+    (params with($($module:ident)::+), $field:expr, $name:expr, $prefix:expr, $params:expr) => {
+        $($module)::+::params(&$field, $prefix, $params)
+    };
+    (set with($($module:ident)::+), $field:expr, $name:expr, $key:expr, $value:expr) => {
+        $($module)::+::set_param(&mut $field, $key, $value)
+    };
+}